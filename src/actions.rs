@@ -0,0 +1,215 @@
+//! Compositor actions - keyboard shortcuts handled by Mirage itself
+
+use smithay::input::keyboard::{Keysym, ModifiersState};
+
+use crate::state::MirageState;
+use crate::tree::Direction;
+
+/// An action the compositor performs instead of forwarding the key to a client
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Switch to the next tiling algorithm
+    CycleLayout,
+    /// Grow or shrink the master area by the given share of the screen
+    AdjustMasterRatio(f32),
+    /// Add or remove master windows
+    AdjustMasterCount(isize),
+    /// Show or hide the dock, reflowing windows into the freed space
+    ToggleDock,
+    /// Float the focused window above the layout, or tile it again
+    ToggleFloating,
+    /// Switch between automatic, manual (container tree) and scrolling tiling
+    CycleTilingMode,
+    /// Flip the split direction used for the next window in manual tiling
+    ToggleNextSplit,
+    /// Flip the split direction of the focused window's container
+    ToggleContainerSplit,
+    /// Remove redundant nesting from the container tree
+    FlattenContainers,
+    /// Move the focused window to the neighbouring container in a direction
+    MoveWindow(Direction),
+    /// Focus the nearest window in a direction
+    FocusDirection(Direction),
+    /// Switch the focused column of the scrolling layout to the next width preset
+    CycleColumnWidth,
+    /// Switch the focused window's container between split, tabbed and stacked
+    CyclePresentation,
+    /// Show the next (true) or previous (false) tab of the focused container
+    CycleTab(bool),
+    /// Ask the focused window to close
+    CloseWindow,
+    /// Hide the focused window until it is restored
+    MinimizeWindow,
+    /// Bring back the most recently minimized window
+    RestoreMinimized,
+    /// Let the focused window cover the usable area, or put it back
+    ToggleMaximize,
+    /// Show the workspace with the given index
+    SwitchWorkspace(usize),
+    /// Move the focused window to the workspace with the given index
+    MoveToWorkspace(usize),
+}
+
+/// How much one key press grows or shrinks the master area
+const MASTER_RATIO_STEP: f32 = 0.05;
+
+/// Map a key press to a compositor action.
+/// All shortcuts use the logo (Super) key as modifier.
+pub fn action_for_key(modifiers: &ModifiersState, keysym: Keysym) -> Option<Action> {
+    if !modifiers.logo {
+        return None;
+    }
+
+    match keysym {
+        Keysym::space if modifiers.shift => Some(Action::ToggleFloating),
+        Keysym::space => Some(Action::CycleLayout),
+        Keysym::h => Some(Action::AdjustMasterRatio(-MASTER_RATIO_STEP)),
+        Keysym::l => Some(Action::AdjustMasterRatio(MASTER_RATIO_STEP)),
+        Keysym::i => Some(Action::AdjustMasterCount(1)),
+        Keysym::d => Some(Action::AdjustMasterCount(-1)),
+        Keysym::b => Some(Action::ToggleDock),
+        Keysym::t => Some(Action::CycleTilingMode),
+        Keysym::r => Some(Action::CycleColumnWidth),
+        Keysym::w => Some(Action::CyclePresentation),
+        Keysym::Tab => Some(Action::CycleTab(true)),
+        Keysym::ISO_Left_Tab => Some(Action::CycleTab(false)),
+        Keysym::s => Some(Action::ToggleNextSplit),
+        Keysym::S => Some(Action::ToggleContainerSplit),
+        Keysym::E => Some(Action::FlattenContainers),
+        Keysym::q => Some(Action::CloseWindow),
+        Keysym::m => Some(Action::MinimizeWindow),
+        Keysym::M => Some(Action::RestoreMinimized),
+        Keysym::f => Some(Action::ToggleMaximize),
+        Keysym::_1 => Some(Action::SwitchWorkspace(0)),
+        Keysym::_2 => Some(Action::SwitchWorkspace(1)),
+        Keysym::_3 => Some(Action::SwitchWorkspace(2)),
+        Keysym::_4 => Some(Action::SwitchWorkspace(3)),
+        // Shifted digits as on a US layout
+        Keysym::exclam => Some(Action::MoveToWorkspace(0)),
+        Keysym::at => Some(Action::MoveToWorkspace(1)),
+        Keysym::numbersign => Some(Action::MoveToWorkspace(2)),
+        Keysym::dollar => Some(Action::MoveToWorkspace(3)),
+        Keysym::Left if modifiers.shift => Some(Action::MoveWindow(Direction::Left)),
+        Keysym::Right if modifiers.shift => Some(Action::MoveWindow(Direction::Right)),
+        Keysym::Up if modifiers.shift => Some(Action::MoveWindow(Direction::Up)),
+        Keysym::Down if modifiers.shift => Some(Action::MoveWindow(Direction::Down)),
+        Keysym::Left => Some(Action::FocusDirection(Direction::Left)),
+        Keysym::Right => Some(Action::FocusDirection(Direction::Right)),
+        Keysym::Up => Some(Action::FocusDirection(Direction::Up)),
+        Keysym::Down => Some(Action::FocusDirection(Direction::Down)),
+        _ => None,
+    }
+}
+
+impl MirageState {
+    /// Perform a compositor action
+    pub fn process_action(&mut self, action: Action) {
+        match action {
+            Action::CycleLayout => {
+                self.layout.cycle_layout();
+                tracing::info!("Switched to {} layout", self.layout.active_layout().name());
+                self.reconfigure_windows();
+            }
+            Action::AdjustMasterRatio(delta) => {
+                self.layout.adjust_master_ratio(delta);
+                tracing::info!("Master ratio set to {:.2}", self.layout.params().master_ratio);
+                self.reconfigure_windows();
+            }
+            Action::AdjustMasterCount(delta) => {
+                self.layout.adjust_master_count(delta);
+                tracing::info!("Master count set to {}", self.layout.params().master_count);
+                self.reconfigure_windows();
+            }
+            Action::ToggleDock => {
+                self.dock.toggle_visibility();
+                tracing::info!("Dock {}", if self.dock.is_visible { "shown" } else { "hidden" });
+                self.update_usable_area();
+                self.reconfigure_windows();
+            }
+            Action::ToggleFloating => {
+                if let Some(id) = self.focused_window {
+                    self.toggle_floating(id);
+                }
+            }
+            Action::CycleTilingMode => {
+                self.layout.cycle_mode();
+                tracing::info!("Switched to {:?} tiling", self.layout.mode());
+                self.scroll_to_focus();
+                self.reconfigure_windows();
+            }
+            Action::ToggleNextSplit => {
+                let split = self.tree.next_split().toggled();
+                self.tree.set_next_split(split);
+                tracing::info!("Next window splits {:?}", split);
+            }
+            Action::ToggleContainerSplit => {
+                if let Some(id) = self.focused_tiled_window() {
+                    self.tree.toggle_split(id);
+                    self.reconfigure_windows();
+                }
+            }
+            Action::FlattenContainers => {
+                self.tree.flatten();
+                self.reconfigure_windows();
+            }
+            Action::MoveWindow(direction) => {
+                if let Some(id) = self.focused_tiled_window() {
+                    if self.tree.move_window(id, direction) {
+                        tracing::info!("Moved window {} {:?}", id, direction);
+                        self.reconfigure_windows();
+                    }
+                }
+            }
+            Action::FocusDirection(direction) => {
+                self.focus_direction(direction);
+            }
+            Action::CyclePresentation => {
+                if let Some(id) = self.focused_tiled_window() {
+                    self.tree.cycle_presentation(id);
+                    self.reconfigure_windows();
+                }
+            }
+            Action::CycleTab(forward) => {
+                if let Some(id) = self.focused_tiled_window() {
+                    if let Some(tab) = self.tree.cycle_tab(id, forward) {
+                        self.set_focus(Some(tab));
+                    }
+                }
+            }
+            Action::CycleColumnWidth => {
+                if let Some(id) = self.focused_tiled_window() {
+                    self.strip.cycle_width(id);
+                    self.scroll_to_focus();
+                    self.reconfigure_windows();
+                }
+            }
+            Action::CloseWindow => {
+                if let Some(window) = self.focused_window.and_then(|id| self.windows.get(id)) {
+                    tracing::info!("Closing window {}", window.id);
+                    window.surface.send_close();
+                }
+            }
+            Action::MinimizeWindow => {
+                if let Some(id) = self.focused_window {
+                    self.minimize_window(id);
+                }
+            }
+            Action::RestoreMinimized => {
+                self.restore_minimized();
+            }
+            Action::ToggleMaximize => {
+                if let Some(id) = self.focused_window {
+                    self.toggle_maximized(id);
+                }
+            }
+            Action::SwitchWorkspace(index) => {
+                self.switch_workspace(index);
+            }
+            Action::MoveToWorkspace(index) => {
+                if let Some(id) = self.focused_window {
+                    self.move_to_workspace(id, index);
+                }
+            }
+        }
+    }
+}
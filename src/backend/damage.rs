@@ -0,0 +1,89 @@
+//! Damage between consecutive frames
+//!
+//! Frames are drawn in full, but only the areas whose content changed since the
+//! previous frame are submitted. Everything drawn is recorded with its area and a
+//! key hashing whatever else decides its pixels, so an element that appears,
+//! disappears, changes or moves in the stacking order damages its area, while a
+//! hovered title bar button damages only its own rectangle.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use smithay::backend::renderer::Color32F;
+use smithay::utils::{Physical, Rectangle};
+
+/// The elements drawn in the current frame and in the previous one
+#[derive(Debug, Default)]
+pub struct FrameDamage {
+    previous: Vec<(Rectangle<i32, Physical>, u64)>,
+    current: Vec<(Rectangle<i32, Physical>, u64)>,
+}
+
+impl FrameDamage {
+    /// Record an element drawn in the current frame, `key` covering what it shows
+    pub fn add(&mut self, rect: Rectangle<i32, Physical>, key: impl Hash) {
+        let mut hasher = DefaultHasher::new();
+        // Elements are recorded bottom to top, so their index stands for the stacking order
+        self.current.len().hash(&mut hasher);
+        key.hash(&mut hasher);
+        self.current.push((rect, hasher.finish()));
+    }
+
+    /// Areas that changed since the previous frame. Starts the next frame.
+    pub fn finish(&mut self) -> Vec<Rectangle<i32, Physical>> {
+        let previous = std::mem::take(&mut self.previous);
+        let current = std::mem::take(&mut self.current);
+        let damage = current
+            .iter()
+            .filter(|element| !previous.contains(element))
+            .chain(previous.iter().filter(|element| !current.contains(element)))
+            .map(|(rect, _)| *rect)
+            .collect();
+        self.previous = current;
+        damage
+    }
+}
+
+/// A color in a form that can be hashed into a key
+pub fn color_key(color: Color32F) -> [u32; 4] {
+    color.components().map(f32::to_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32) -> Rectangle<i32, Physical> {
+        Rectangle::new((x, y).into(), (20, 20).into())
+    }
+
+    #[test]
+    fn test_only_changed_elements_damage() {
+        let mut damage = FrameDamage::default();
+        damage.add(rect(0, 0), "background");
+        damage.add(rect(10, 10), "button");
+        assert_eq!(damage.finish(), vec![rect(0, 0), rect(10, 10)]);
+
+        damage.add(rect(0, 0), "background");
+        damage.add(rect(10, 10), "button");
+        assert!(damage.finish().is_empty());
+
+        // A hovered button shows its glyph
+        damage.add(rect(0, 0), "background");
+        damage.add(rect(10, 10), "button with glyph");
+        assert_eq!(damage.finish(), vec![rect(10, 10), rect(10, 10)]);
+    }
+
+    #[test]
+    fn test_moved_and_removed_elements_damage() {
+        let mut damage = FrameDamage::default();
+        damage.add(rect(0, 0), "background");
+        damage.add(rect(50, 50), "cursor");
+        damage.add(rect(80, 80), "menu");
+        damage.finish();
+
+        damage.add(rect(0, 0), "background");
+        damage.add(rect(60, 50), "cursor");
+        assert_eq!(damage.finish(), vec![rect(60, 50), rect(50, 50), rect(80, 80)]);
+    }
+}
@@ -0,0 +1,328 @@
+//! GLES shader effects: rounded window corners, drop shadows and title bar buttons
+//!
+//! Frames are rendered with `Transform::Normal`, so fragment coordinates are
+//! output coordinates and the texture shader can clip every surface of a
+//! window against the same rounded rectangle, subsurfaces included.
+
+use smithay::backend::renderer::gles::{
+    GlesError, GlesFrame, GlesPixelProgram, GlesRenderer, GlesTexProgram, Uniform, UniformName, UniformType,
+};
+use smithay::backend::renderer::Color32F;
+use smithay::utils::{Logical, Physical, Rectangle};
+
+use crate::backend::damage::{color_key, FrameDamage};
+use crate::decorations::{DecorationElement, WindowStyle};
+
+/// Shadow opacity beneath the focused window
+const FOCUSED_SHADOW_STRENGTH: f32 = 0.55;
+/// Shadow opacity beneath other windows
+const UNFOCUSED_SHADOW_STRENGTH: f32 = 0.25;
+
+/// Signed distance from a point to a rounded rectangle at the origin, with
+/// radii for the top-left, top-right, bottom-right and bottom-left corners
+const ROUNDED_RECT_SDF: &str = r#"
+float rounded_rect_distance(vec2 pos, vec2 size, vec4 radii) {
+    vec2 half_size = size * 0.5;
+    vec2 rel = pos - half_size;
+    float radius = rel.x < 0.0
+        ? (rel.y < 0.0 ? radii.x : radii.w)
+        : (rel.y < 0.0 ? radii.y : radii.z);
+    vec2 q = abs(rel) - (half_size - vec2(radius));
+    return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
+}
+"#;
+
+/// Client surfaces, faded out past the window's rounded corners
+const CLIPPED_TEXTURE_SHADER: &str = r#"#version 100
+
+//_DEFINES_
+
+#if defined(EXTERNAL)
+#extension GL_OES_EGL_image_external : require
+#endif
+
+precision mediump float;
+#if defined(EXTERNAL)
+uniform samplerExternalOES tex;
+#else
+uniform sampler2D tex;
+#endif
+
+uniform float alpha;
+varying vec2 v_coords;
+
+#if defined(DEBUG_FLAGS)
+uniform float tint;
+#endif
+
+uniform vec4 geo;
+uniform vec4 radii;
+
+//_SDF_
+
+void main() {
+    vec4 color = texture2D(tex, v_coords);
+
+#if defined(NO_ALPHA)
+    color = vec4(color.rgb, 1.0) * alpha;
+#else
+    color = color * alpha;
+#endif
+
+    float distance = rounded_rect_distance(gl_FragCoord.xy - geo.xy, geo.zw, radii);
+    color = color * clamp(0.5 - distance, 0.0, 1.0);
+
+#if defined(DEBUG_FLAGS)
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
+"#;
+
+/// A solid color filling a rounded rectangle
+const ROUNDED_RECT_SHADER: &str = r#"
+precision mediump float;
+uniform float alpha;
+uniform vec2 size;
+varying vec2 v_coords;
+
+uniform vec4 color;
+uniform vec4 radii;
+
+//_SDF_
+
+void main() {
+    float distance = rounded_rect_distance(v_coords * size, size, radii);
+    gl_FragColor = color * alpha * clamp(0.5 - distance, 0.0, 1.0);
+}
+"#;
+
+/// A soft black shadow falling off outside a rounded rectangle
+const SHADOW_SHADER: &str = r#"
+precision mediump float;
+uniform float alpha;
+uniform vec2 size;
+varying vec2 v_coords;
+
+uniform vec4 shape;
+uniform float radius;
+uniform float blur;
+
+//_SDF_
+
+void main() {
+    float distance = rounded_rect_distance(v_coords * size - shape.xy, shape.zw, vec4(radius));
+    float strength = 1.0 - smoothstep(-blur * 0.5, blur, distance);
+    gl_FragColor = vec4(0.0, 0.0, 0.0, strength * strength * alpha);
+}
+"#;
+
+/// A round title bar button with an optional dark glyph: 1 is ×, 2 is − and 3 is +
+const BUTTON_SHADER: &str = r#"
+precision mediump float;
+uniform float alpha;
+uniform vec2 size;
+varying vec2 v_coords;
+
+uniform vec4 color;
+uniform float glyph;
+
+float segment_distance(vec2 p, vec2 a, vec2 b) {
+    vec2 pa = p - a;
+    vec2 ba = b - a;
+    float h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h);
+}
+
+void main() {
+    vec2 center = size * 0.5;
+    vec2 pos = v_coords * size - center;
+    float radius = min(center.x, center.y);
+    float circle = clamp(radius - length(pos) + 0.5, 0.0, 1.0);
+
+    float arm = radius * 0.45;
+    float distance = 1000.0;
+    if (glyph == 1.0) {
+        distance = min(segment_distance(pos, vec2(-arm), vec2(arm)),
+                       segment_distance(pos, vec2(-arm, arm), vec2(arm, -arm)));
+    } else if (glyph >= 2.0) {
+        distance = segment_distance(pos, vec2(-arm * 1.2, 0.0), vec2(arm * 1.2, 0.0));
+        if (glyph == 3.0) {
+            distance = min(distance, segment_distance(pos, vec2(0.0, -arm * 1.2), vec2(0.0, arm * 1.2)));
+        }
+    }
+    float line = clamp(1.25 - distance, 0.0, 1.0);
+
+    vec3 rgb = mix(color.rgb, color.rgb * 0.35, line);
+    gl_FragColor = vec4(rgb, 1.0) * color.a * circle * alpha;
+}
+"#;
+
+/// Compiled shaders drawing windows in the configured [`WindowStyle`]
+pub struct Effects {
+    pub style: WindowStyle,
+    clipped_texture: GlesTexProgram,
+    rounded_rect: GlesPixelProgram,
+    shadow: GlesPixelProgram,
+    button: GlesPixelProgram,
+}
+
+impl Effects {
+    pub fn new(renderer: &mut GlesRenderer, style: WindowStyle) -> Result<Self, GlesError> {
+        let clipped_texture = renderer.compile_custom_texture_shader(
+            CLIPPED_TEXTURE_SHADER.replace("//_SDF_", ROUNDED_RECT_SDF),
+            &[
+                UniformName::new("geo", UniformType::_4f),
+                UniformName::new("radii", UniformType::_4f),
+            ],
+        )?;
+        let rounded_rect = renderer.compile_custom_pixel_shader(
+            ROUNDED_RECT_SHADER.replace("//_SDF_", ROUNDED_RECT_SDF),
+            &[
+                UniformName::new("color", UniformType::_4f),
+                UniformName::new("radii", UniformType::_4f),
+            ],
+        )?;
+        let shadow = renderer.compile_custom_pixel_shader(
+            SHADOW_SHADER.replace("//_SDF_", ROUNDED_RECT_SDF),
+            &[
+                UniformName::new("shape", UniformType::_4f),
+                UniformName::new("radius", UniformType::_1f),
+                UniformName::new("blur", UniformType::_1f),
+            ],
+        )?;
+
+        let button = renderer.compile_custom_pixel_shader(
+            BUTTON_SHADER,
+            &[
+                UniformName::new("color", UniformType::_4f),
+                UniformName::new("glyph", UniformType::_1f),
+            ],
+        )?;
+
+        Ok(Self {
+            style,
+            clipped_texture,
+            rounded_rect,
+            shadow,
+            button,
+        })
+    }
+
+    /// Clip the client surfaces drawn until [`Effects::end_clip`] to a rounded rectangle
+    pub fn begin_clip(&self, frame: &mut GlesFrame<'_, '_>, rect: Rectangle<i32, Logical>, radii: [f32; 4]) {
+        let geo = [rect.loc.x as f32, rect.loc.y as f32, rect.size.w as f32, rect.size.h as f32];
+        frame.override_default_tex_program(
+            self.clipped_texture.clone(),
+            vec![Uniform::new("geo", geo), Uniform::new("radii", radii)],
+        );
+    }
+
+    pub fn end_clip(&self, frame: &mut GlesFrame<'_, '_>) {
+        frame.clear_tex_program_override();
+    }
+
+    /// Fill a rectangle, rounding the corners with the given radii
+    pub fn draw_rounded_rect(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        rect: Rectangle<i32, Logical>,
+        radii: [f32; 4],
+        color: Color32F,
+        damage: &mut FrameDamage,
+    ) -> Result<(), GlesError> {
+        let dest = physical(rect);
+        frame.render_pixel_shader_to(
+            &self.rounded_rect,
+            Rectangle::from_size((rect.size.w as f64, rect.size.h as f64).into()),
+            dest,
+            (rect.size.w, rect.size.h).into(),
+            None,
+            1.0,
+            &[
+                Uniform::new("color", [color.r(), color.g(), color.b(), color.a()]),
+                Uniform::new("radii", radii),
+            ],
+        )?;
+        damage.add(dest, (radii.map(f32::to_bits), color_key(color)));
+        Ok(())
+    }
+
+    /// Draw a round title bar button, with the glyph of its action if given
+    pub fn draw_button(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        rect: Rectangle<i32, Logical>,
+        color: Color32F,
+        glyph: Option<DecorationElement>,
+        damage: &mut FrameDamage,
+    ) -> Result<(), GlesError> {
+        let glyph = match glyph {
+            Some(DecorationElement::CloseButton) => 1.0,
+            Some(DecorationElement::MinimizeButton) => 2.0,
+            Some(DecorationElement::MaximizeButton) => 3.0,
+            _ => 0.0,
+        };
+        let dest = physical(rect);
+        frame.render_pixel_shader_to(
+            &self.button,
+            Rectangle::from_size((rect.size.w as f64, rect.size.h as f64).into()),
+            dest,
+            (rect.size.w, rect.size.h).into(),
+            None,
+            1.0,
+            &[
+                Uniform::new("color", [color.r(), color.g(), color.b(), color.a()]),
+                Uniform::new("glyph", glyph),
+            ],
+        )?;
+        damage.add(dest, (color_key(color), glyph.to_bits()));
+        Ok(())
+    }
+
+    /// Draw the shadow beneath a window, stronger for the focused one
+    pub fn draw_shadow(
+        &self,
+        frame: &mut GlesFrame<'_, '_>,
+        window: Rectangle<i32, Logical>,
+        focused: bool,
+        damage: &mut FrameDamage,
+    ) -> Result<(), GlesError> {
+        let blur = self.style.shadow_size;
+        if blur <= 0 {
+            return Ok(());
+        }
+
+        // The light comes from above, so the shadow reaches further down
+        let offset = blur / 4;
+        let area = Rectangle::new(
+            (window.loc.x - blur, window.loc.y - blur + offset).into(),
+            (window.size.w + 2 * blur, window.size.h + 2 * blur).into(),
+        );
+        let shape = [blur as f32, (blur - offset) as f32, window.size.w as f32, window.size.h as f32];
+        let strength = if focused { FOCUSED_SHADOW_STRENGTH } else { UNFOCUSED_SHADOW_STRENGTH };
+
+        let dest = physical(area);
+        frame.render_pixel_shader_to(
+            &self.shadow,
+            Rectangle::from_size((area.size.w as f64, area.size.h as f64).into()),
+            dest,
+            (area.size.w, area.size.h).into(),
+            None,
+            strength,
+            &[
+                Uniform::new("shape", shape),
+                Uniform::new("radius", self.style.corner_radius as f32),
+                Uniform::new("blur", blur as f32),
+            ],
+        )?;
+        damage.add(dest, strength.to_bits());
+        Ok(())
+    }
+}
+
+fn physical(rect: Rectangle<i32, Logical>) -> Rectangle<i32, Physical> {
+    Rectangle::new((rect.loc.x, rect.loc.y).into(), (rect.size.w, rect.size.h).into())
+}
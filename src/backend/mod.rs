@@ -1 +1,3 @@
 pub mod winit;
+pub mod effects;
+pub mod damage;
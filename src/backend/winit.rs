@@ -1,15 +1,16 @@
 use std::time::Duration;
 use std::sync::Arc;
+use std::collections::HashMap;
 
 use smithay::{
     backend::winit::{self, WinitEvent},
     backend::renderer::{
-        gles::GlesRenderer,
+        gles::{GlesFrame, GlesRenderer, GlesTexture},
         element::{
             surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
-            Kind,
+            Element, Id, Kind,
         },
-        utils::draw_render_elements,
+        utils::{draw_render_elements, CommitCounter},
         Color32F,
     },
     reexports::{
@@ -18,20 +19,43 @@ use smithay::{
         wayland_server::ListeningSocket,
     },
     output::{Mode, Output, PhysicalProperties, Subpixel},
+    desktop::utils::bbox_from_surface_tree,
     utils::Transform,
 };
 use tracing::{error, info};
 
 use crate::state::{MirageState, ClientState};
+use crate::actions::{action_for_key, Action};
+use crate::tree::TabStrip;
+use crate::window::WindowId;
+use crate::window_menu::{WindowMenu, MENU_PADDING};
+use crate::decorations::DecorationElement;
+use crate::text::TitleRenderer;
+use crate::backend::damage::{color_key, FrameDamage};
+use crate::backend::effects::Effects;
 
 pub const OUTPUT_NAME: &str = "winit";
 
+/// Linux input event code of the left mouse button
+const BTN_LEFT: u32 = 0x110;
+
+/// Linux input event code of the right mouse button
+const BTN_RIGHT: u32 = 0x111;
+
+/// Space between a tab's edges and its title
+const TAB_TEXT_PADDING: i32 = 8;
+
+/// Space between a menu entry's edges and its label or check mark
+const MENU_TEXT_PADDING: i32 = 10;
+/// Diameter of the dot marking checked menu entries
+const MENU_CHECK_SIZE: i32 = 8;
+
 // Global serial counter for input events
-static SERIAL_COUNTER: smithay::utils::SerialCounter = smithay::utils::SerialCounter::new();
+pub static SERIAL_COUNTER: smithay::utils::SerialCounter = smithay::utils::SerialCounter::new();
 
 pub fn run_winit_backend<S: 'static>() {
     let mut event_loop = EventLoop::try_new().unwrap();
-    let display: smithay::reexports::wayland_server::Display<MirageState> = 
+    let mut display: smithay::reexports::wayland_server::Display<MirageState> = 
         smithay::reexports::wayland_server::Display::new().unwrap();
     let mut display_handle = display.handle();
 
@@ -64,6 +88,8 @@ pub fn run_winit_backend<S: 'static>() {
 
     let mut state = MirageState::new(&display_handle);
     state.output = Some(output.clone());
+    state.layout.update_screen_size(size.w, size.h);
+    state.update_usable_area();
     state.initialize_seat(&display_handle);
 
     // Create a listening socket for Wayland clients
@@ -83,6 +109,15 @@ pub fn run_winit_backend<S: 'static>() {
     std::env::set_var("WAYLAND_DISPLAY", socket_name);
     
     let mut clients = Vec::new();
+    let mut titles = TitleRenderer::new(state.config.title_font.clone());
+    let effects = match Effects::new(backend.renderer(), state.config.window_style) {
+        Ok(effects) => effects,
+        Err(err) => {
+            error!("Failed to compile window shaders: {}", err);
+            return;
+        }
+    };
+    let mut damage = FrameDamage::default();
 
     info!("Initialization completed, starting the main loop.");
     info!("Mirage Compositor running at {}x{}", size.w, size.h);
@@ -98,6 +133,8 @@ pub fn run_winit_backend<S: 'static>() {
                     output.change_current_state(Some(mode), None, None, None);
                     output.set_preferred(mode);
                     state.layout.update_screen_size(size.w, size.h);
+                    state.update_usable_area();
+                    state.reconfigure_windows();
                     info!("Output resized to {}x{}", size.w, size.h);
                 }
             }
@@ -118,15 +155,27 @@ pub fn run_winit_backend<S: 'static>() {
                         state.pointer_pos.x = x;
                         state.pointer_pos.y = y;
                         info!("Pointer absolute position ({:.1}, {:.1})", x, y);
+                        state.update_decoration_hover(state.pointer_pos);
+                        state.update_move(state.pointer_pos);
+                        state.update_resize(state.pointer_pos);
+                        if let Some(menu) = state.window_menu.as_mut() {
+                            if let Some(entry) = menu.entry_at(state.pointer_pos) {
+                                menu.selected = Some(entry);
+                            }
+                        }
                         
                         // Route to pointer device - clone to avoid borrow checker issues
                         if let Some(pointer) = state.pointer.clone() {
                             // Update pointer focus based on window under cursor
-                            let focus = if let Some(idx) = state.window_at(state.pointer_pos) {
-                                state.windows.get(idx).map(|s| s.wl_surface().clone())
-                            } else {
-                                None
-                            };
+                            // Clients get coordinates relative to their surface, not the tile
+                            // Clients don't see the pointer while a window is dragged or resized, or over the menu
+                            let over_menu = state
+                                .window_menu
+                                .as_ref()
+                                .is_some_and(|menu| menu.contains(state.pointer_pos));
+                            let focus = state
+                                .surface_under(state.pointer_pos)
+                                .filter(|_| state.move_grab.is_none() && state.resize_grab.is_none() && !over_menu);
                             
                             let pointer_pos = state.pointer_pos;
                             let motion_event = MotionEvent {
@@ -135,27 +184,107 @@ pub fn run_winit_backend<S: 'static>() {
                                 time: event.time_msec(),
                             };
                             
-                            pointer.motion(&mut state, focus.map(|s| (s, pointer_pos)), &motion_event);
+                            pointer.motion(&mut state, focus, &motion_event);
                             pointer.frame(&mut state);
                         }
                     }
                     InputEvent::PointerButton { event } => {
+                        let button = event.button_code();
+                        let mut consumed = false;
+
                         // Mouse click - check which window is under cursor and set focus
                         if event.state() == ButtonState::Pressed {
-                            if let Some(idx) = state.window_at(state.pointer_pos) {
-                                state.set_focus(Some(idx));
-                                info!("Clicked on window {}", idx);
+                            if let Some(menu) = &state.window_menu {
+                                // Choose an entry, or close the menu by clicking anywhere else
+                                let item = menu
+                                    .entry_at(state.pointer_pos)
+                                    .map(|entry| menu.entries[entry].item);
+                                state.activate_menu_item(item);
+                                state.suppressed_buttons.push(button);
+                                consumed = true;
+                            } else if state.popup_grab_active() {
+                                // The click goes to the popup grab, which dismisses the popup outside its client
+                                info!("Clicked during a popup grab");
+                            } else if state.popup_at(state.pointer_pos).is_some() {
+                                // Popups may cover title bars and borders, the click is for their client
+                                info!("Clicked on a popup");
+                            } else if let Some(id) = state
+                                .title_bar_at(state.pointer_pos)
+                                .filter(|_| button == BTN_RIGHT)
+                            {
+                                let pos = state.pointer_pos.to_i32_round();
+                                state.open_window_menu(id, pos);
+                                state.suppressed_buttons.push(button);
+                                consumed = true;
+                            } else if let Some((id, element)) = state
+                                .decoration_button_at(state.pointer_pos)
+                                .filter(|_| button == BTN_LEFT)
+                            {
+                                // Title bar buttons act on release, the client never sees the click
+                                state.press_decoration_button(id, element);
+                                state.suppressed_buttons.push(button);
+                                consumed = true;
+                            } else if let Some((id, edges)) = state
+                                .resize_edges_at(state.pointer_pos)
+                                .filter(|_| button == BTN_LEFT)
+                            {
+                                // Borders around decorated windows resize them
+                                state.set_focus(Some(id));
+                                state.begin_resize(id, edges, button);
+                                state.suppressed_buttons.push(button);
+                                consumed = true;
+                            } else if let Some(id) = state
+                                .title_bar_at(state.pointer_pos)
+                                .filter(|_| button == BTN_LEFT)
+                            {
+                                // Title bars belong to the compositor: double-click maximizes, dragging moves
+                                state.click_title_bar(id, button, event.time_msec());
+                                state.suppressed_buttons.push(button);
+                                consumed = true;
+                            } else if let Some(id) = state.tab_at(state.pointer_pos) {
+                                // Clicking a tab shows its window, the click isn't meant for the client
+                                state.set_focus(Some(id));
+                                state.suppressed_buttons.push(button);
+                                consumed = true;
+                                info!("Clicked tab of window {}", id);
+                            } else if let Some(id) = state.window_at(state.pointer_pos) {
+                                state.set_focus(Some(id));
+                                info!("Clicked on window {}", id);
                             } else {
                                 state.set_focus(None);
                                 info!("Clicked on empty space");
                             }
+                        } else {
+                            // Moves and resizes end when the button that started them is released
+                            if state.move_grab.is_some_and(|grab| grab.button == button) {
+                                state.end_move(state.pointer_pos);
+                            }
+                            if state.resize_grab.is_some_and(|grab| grab.button == button) {
+                                state.end_resize();
+                            }
+                            if button == BTN_LEFT {
+                                if let Some((id, element)) = state.release_decoration_button(state.pointer_pos) {
+                                    let action = match element {
+                                        DecorationElement::CloseButton => Action::CloseWindow,
+                                        DecorationElement::MinimizeButton => Action::MinimizeWindow,
+                                        _ => Action::ToggleMaximize,
+                                    };
+                                    state.set_focus(Some(id));
+                                    state.process_action(action);
+                                    info!("Clicked {:?} of window {}", element, id);
+                                }
+                            }
+                            if let Some(pos) = state.suppressed_buttons.iter().position(|b| *b == button) {
+                                state.suppressed_buttons.remove(pos);
+                                consumed = true;
+                            }
                         }
                         
                         // Route button event to pointer device - clone to avoid borrow checker issues
-                        if let Some(pointer) = state.pointer.clone() {
+                        if let Some(pointer) = state.pointer.clone().filter(|_| !consumed) {
                             use smithay::input::pointer::ButtonEvent as PointerButtonEvent;
                             let button_event = PointerButtonEvent {
-                                button: event.button_code(),
+                                button,
                                 state: event.state(),
                                 serial: SERIAL_COUNTER.next_serial(),
                                 time: event.time_msec(),
@@ -174,14 +303,37 @@ pub fn run_winit_backend<S: 'static>() {
                         info!("Keyboard event");
                         if let Some(keyboard) = state.keyboard.clone() {
                             use smithay::input::keyboard::FilterResult;
-                            keyboard.input::<(), _>(
+                            use smithay::backend::input::KeyState;
+                            let key_code = event.key_code();
+                            let key_state = event.state();
+                            // Compositor shortcuts are intercepted, everything else goes to clients
+                            let action = keyboard.input::<Option<Action>, _>(
                                 &mut state,
-                                event.key_code(),
-                                event.state(),
+                                key_code,
+                                key_state,
                                 SERIAL_COUNTER.next_serial(),
                                 event.time_msec(),
-                                |_, _, _| FilterResult::Forward, // Forward all keys to clients
+                                |state, modifiers, handle| {
+                                    if key_state == KeyState::Pressed {
+                                        // The open window menu takes the keys it navigates with
+                                        if state.window_menu_key(handle.modified_sym()) {
+                                            state.suppressed_keys.push(key_code);
+                                            return FilterResult::Intercept(None);
+                                        }
+                                        if let Some(action) = action_for_key(modifiers, handle.modified_sym()) {
+                                            state.suppressed_keys.push(key_code);
+                                            return FilterResult::Intercept(Some(action));
+                                        }
+                                    } else if let Some(pos) = state.suppressed_keys.iter().position(|k| *k == key_code) {
+                                        state.suppressed_keys.remove(pos);
+                                        return FilterResult::Intercept(None);
+                                    }
+                                    FilterResult::Forward
+                                },
                             );
+                            if let Some(Some(action)) = action {
+                                state.process_action(action);
+                            }
                         }
                     }
                     _ => {}
@@ -209,17 +361,32 @@ pub fn run_winit_backend<S: 'static>() {
         }
 
         // Render a frame
-        if let Err(err) = render_frame(&state, &mut backend) {
+        if let Err(err) = render_frame(&state, &mut backend, &mut titles, &effects, &mut damage) {
             error!("Rendering error: {}", err);
         }
+        state.popups.cleanup();
 
         let result = event_loop.dispatch(Some(Duration::from_millis(1)), &mut state);
         if result.is_err() {
             info!("Event loop error, shutting down...");
             break;
-        } else {
-            display_handle.flush_clients().ok();
         }
+
+        // Process requests from clients, then forget the ones that went away
+        if let Err(err) = display.dispatch_clients(&mut state) {
+            error!("Failed to dispatch clients: {}", err);
+        }
+        let connected = clients.len();
+        clients.retain(|client| {
+            !client
+                .get_data::<ClientState>()
+                .is_some_and(|data| data.is_disconnected())
+        });
+        if clients.len() != connected {
+            state.remove_dead_windows();
+        }
+
+        display_handle.flush_clients().ok();
     }
 
     info!("Mirage Compositor shutdown complete.");
@@ -228,39 +395,101 @@ pub fn run_winit_backend<S: 'static>() {
 fn render_frame(
     state: &MirageState,
     backend: &mut smithay::backend::winit::WinitGraphicsBackend<GlesRenderer>,
+    titles: &mut TitleRenderer,
+    effects: &Effects,
+    damage: &mut FrameDamage,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use smithay::utils::{Rectangle, Transform};
     use smithay::backend::renderer::{Renderer, Frame};
     
     let size = backend.window_size();
-    let mut damage_rects = Vec::new();
     
     // First, collect all render elements BEFORE creating the frame
     // We need to bind renderer, but NOT create frame yet
-    let all_elements = {
+    let tab_strips = state.tab_strips();
+    let (all_elements, tab_titles, menu_labels) = {
         let (renderer, _) = backend.bind()?;
         
         let mut all_window_elements = Vec::new();
         
-        // Collect render elements for each window
-        for (idx, window) in state.windows.iter().enumerate() {
-            let geom = state.layout.calculate_geometry(idx, state.windows.len());
-            let location = (geom.location.x, geom.location.y);
+        // Collect render elements for each window, bottom to top
+        for id in state.stacking_order() {
+            let Some(window) = state.windows.get(id) else {
+                continue;
+            };
+            let geom = state.constrained_geometry(id);
+            // The window geometry goes below the title bar, any client-drawn shadow around it
+            let Some((content, origin)) = state.surface_placement(id) else {
+                continue;
+            };
+            let location = (origin.x, origin.y);
+            let surface_rect = bbox_from_surface_tree(window.surface.wl_surface(), origin);
             
             let elements: Vec<WaylandSurfaceRenderElement<GlesRenderer>> = 
                 render_elements_from_surface_tree(
                     renderer,
-                    window.wl_surface(),
+                    window.surface.wl_surface(),
                     location,
                     1.0, // scale
                     1.0, // alpha
                     Kind::Unspecified,
                 );
             
-            all_window_elements.push((idx, geom, elements));
+            // Title textures have to be uploaded before the frame starts
+            let decoration = &window.decoration;
+            let title = if decoration.has_title_bar() {
+                titles.texture(renderer, &decoration.title, decoration.title_text_rect().size.w)
+            } else {
+                None
+            };
+            
+            // Popups go above their window, each below its own children
+            let popups: Vec<_> = state
+                .window_popups(id)
+                .into_iter()
+                .rev()
+                .map(|(popup, location)| {
+                    let surface = popup.wl_surface();
+                    let rect = bbox_from_surface_tree(surface, location).to_physical(1);
+                    let elements: Vec<WaylandSurfaceRenderElement<GlesRenderer>> = render_elements_from_surface_tree(
+                        renderer,
+                        surface,
+                        (location.x, location.y),
+                        1.0,
+                        1.0,
+                        Kind::Unspecified,
+                    );
+                    (rect, elements)
+                })
+                .collect();
+            
+            all_window_elements.push((id, geom, content, surface_rect, elements, title, popups));
         }
+
+        let mut tab_titles = HashMap::new();
+        for strip in &tab_strips {
+            for (tab, (&id, title)) in strip.tabs.iter().zip(&strip.titles).enumerate() {
+                let width = strip.tab_rect(tab).size.w - 2 * TAB_TEXT_PADDING;
+                if let Some(texture) = titles.texture(renderer, title, width) {
+                    tab_titles.insert(id, texture);
+                }
+            }
+        }
+        
+        // Labels of the window menu entries, one per entry
+        let menu_labels: Vec<Option<GlesTexture>> = state
+            .window_menu
+            .iter()
+            .flat_map(|menu| {
+                let width = menu.entry_rect(0).size.w - 3 * MENU_TEXT_PADDING - MENU_CHECK_SIZE;
+                menu.entries
+                    .iter()
+                    .map(move |entry| (entry.label.as_str(), width))
+            })
+            .map(|(label, width)| titles.texture(renderer, label, width))
+            .collect();
         
-        all_window_elements
+        (all_window_elements, tab_titles, menu_labels)
     };
     
     // Now bind again and render
@@ -269,126 +498,305 @@ fn render_frame(
         let mut frame = renderer.render(&mut target, size, Transform::Normal)?;
         
         // Clear to background color (dark gray)
-        let screen_rect = Rectangle::from_loc_and_size((0, 0), (size.w, size.h));
+        let screen_rect = Rectangle::from_size(size);
         // Use slightly lighter color so we can see something
-        frame.clear(Color32F::new(0.25, 0.25, 0.25, 1.0), &[screen_rect])?;
-        info!("Rendering frame at {}x{}, {} windows, {} decorations", 
-              size.w, size.h, state.windows.len(), state.decorations.len());
+        let background = Color32F::new(0.25, 0.25, 0.25, 1.0);
+        frame.clear(background, &[screen_rect])?;
+        damage.add(screen_rect, color_key(background));
+        info!("Rendering frame at {}x{}, {} windows", size.w, size.h, state.windows.len());
         
+        // Tab strips sit on top of the tiled windows but below maximized and floating ones
+        let mut tab_strips_drawn = false;
+
         // Render each window's collected elements with decorations
-        for (idx, geom, elements) in all_elements {
-            let window_rect = Rectangle::from_loc_and_size((geom.location.x, geom.location.y), (geom.size.w, geom.size.h));
+        for (id, geom, content, surface_rect, elements, title, popups) in all_elements {
+            if !tab_strips_drawn && (state.is_floating(id) || state.is_maximized(id) || state.is_fullscreen(id)) {
+                draw_tab_strips(&mut frame, state, &tab_strips, &tab_titles, damage)?;
+                tab_strips_drawn = true;
+            }
+
+            // Maximized and fullscreen windows fill their area edge to edge
+            let is_focused = Some(id) == state.focused_window;
+            let edge_to_edge = state.is_maximized(id) || state.is_fullscreen(id);
+            let radius = if edge_to_edge { 0.0 } else { effects.style.corner_radius as f32 };
+            let server_side = state.windows.get(id).is_some_and(|window| window.decoration.is_server_side);
+            // With a title bar above it, only the bottom corners of the content are rounded.
+            // Clients drawing their own decorations round their corners and cast their shadow
+            // themselves, so their whole surface is drawn as it is.
+            let (window_rect, content_radii) = if server_side {
+                (content.to_physical(1), [0.0, 0.0, radius, radius])
+            } else {
+                (surface_rect.to_physical(1), [0.0; 4])
+            };
+
+            if server_side && !edge_to_edge {
+                effects.draw_shadow(&mut frame, geom.rect(), is_focused, damage)?;
+            }
             
             // Draw elements if any exist
             if !elements.is_empty() {
-                draw_render_elements(&mut frame, 1.0, &elements, &[window_rect])?;
-                damage_rects.push(window_rect);
+                if server_side {
+                    effects.begin_clip(&mut frame, content, content_radii);
+                }
+                let drawn = draw_render_elements(&mut frame, 1.0, &elements, &[window_rect]);
+                if server_side {
+                    effects.end_clip(&mut frame);
+                }
+                drawn?;
+                damage.add(window_rect, (surface_key(&elements), content_radii.map(f32::to_bits)));
                 info!("Rendered window {} with surfaces at ({},{}) size({}x{})", 
-                      idx, geom.location.x, geom.location.y, geom.size.w, geom.size.h);
+                      id, content.loc.x, content.loc.y, content.size.w, content.size.h);
             } else {
                 // Fallback: draw colored rectangle if no surface content
-                let color = if Some(idx) == state.focused_window {
+                let color = if Some(id) == state.focused_window {
                     Color32F::new(0.2, 0.5, 0.9, 1.0) // Blue for focused
                 } else {
                     Color32F::new(0.3, 0.3, 0.3, 1.0) // Dark gray for unfocused
                 };
                 
-                frame.draw_solid(window_rect, &[window_rect], color)?;
-                damage_rects.push(window_rect);
+                effects.draw_rounded_rect(&mut frame, content, content_radii, color, damage)?;
             }
             
-            // Draw window decorations (title bar and buttons)
-            if idx < state.decorations.len() {
-                let _decoration = &state.decorations[idx];
-                let is_focused = Some(idx) == state.focused_window;
-                
-                // Draw title bar
-                let title_bar_rect = Rectangle::from_loc_and_size(
-                    (geom.location.x, geom.location.y),
-                    (geom.size.w, 32),
-                );
-                
-                let title_bar_color = if is_focused {
-                    Color32F::new(0.3, 0.3, 0.3, 1.0) // Dark gray for focused
-                } else {
-                    Color32F::new(0.2, 0.2, 0.2, 1.0) // Darker gray for unfocused
-                };
+            // Draw window decorations (title bar and buttons), unless the client draws its own
+            if let Some(window) = state.windows.get(id).filter(|window| window.decoration.has_title_bar()) {
+                // Draw title bar, rounded at the top
+                let mut title_bar_rect = window.decoration.title_bar_rect();
+                title_bar_rect.loc += geom.location;
                 
-                frame.draw_solid(title_bar_rect, &[title_bar_rect], title_bar_color)?;
-                damage_rects.push(title_bar_rect);
+                let (r, g, b, a) = window.decoration.title_bar_color();
+                effects.draw_rounded_rect(
+                    &mut frame,
+                    title_bar_rect,
+                    [radius, radius, 0.0, 0.0],
+                    Color32F::new(r, g, b, a),
+                    damage,
+                )?;
                 
-                // Draw close button (red)
-                let close_btn_rect = Rectangle::from_loc_and_size(
-                    (geom.location.x + geom.size.w - 32, geom.location.y + 6),
-                    (20, 20),
-                );
-                frame.draw_solid(close_btn_rect, &[close_btn_rect], Color32F::new(0.9, 0.2, 0.2, 1.0))?;
-                damage_rects.push(close_btn_rect);
-                
-                // Draw minimize button (yellow)
-                let minimize_btn_rect = Rectangle::from_loc_and_size(
-                    (geom.location.x + geom.size.w - 62, geom.location.y + 6),
-                    (20, 20),
-                );
-                frame.draw_solid(minimize_btn_rect, &[minimize_btn_rect], Color32F::new(0.9, 0.8, 0.2, 1.0))?;
-                damage_rects.push(minimize_btn_rect);
-                
-                // Draw maximize button (green)
-                let maximize_btn_rect = Rectangle::from_loc_and_size(
-                    (geom.location.x + geom.size.w - 92, geom.location.y + 6),
-                    (20, 20),
-                );
-                frame.draw_solid(maximize_btn_rect, &[maximize_btn_rect], Color32F::new(0.2, 0.9, 0.2, 1.0))?;
-                damage_rects.push(maximize_btn_rect);
+                // Draw the traffic-light buttons, hovering and pressing them only damages the buttons
+                let decoration = &window.decoration;
+                let buttons = [
+                    (DecorationElement::CloseButton, decoration.close_button_rect(), decoration.close_button_color()),
+                    (DecorationElement::MinimizeButton, decoration.minimize_button_rect(), decoration.minimize_button_color()),
+                    (DecorationElement::MaximizeButton, decoration.maximize_button_rect(), decoration.maximize_button_color()),
+                ];
+                for (element, mut rect, (r, g, b, a)) in buttons {
+                    rect.loc += geom.location;
+                    let glyph = decoration.is_hovered.then_some(element);
+                    effects.draw_button(&mut frame, rect, Color32F::new(r, g, b, a), glyph, damage)?;
+                }
+
+                // Draw the title between the left edge and the buttons
+                if let Some(texture) = &title {
+                    let mut area = window.decoration.title_text_rect();
+                    area.loc += geom.location;
+                    let alpha = if is_focused { 1.0 } else { 0.6 };
+                    draw_text(&mut frame, texture, area, alpha, damage)?;
+                }
+            }
+
+            for (popup_rect, elements) in &popups {
+                draw_render_elements(&mut frame, 1.0, elements, &[*popup_rect])?;
+                damage.add(*popup_rect, surface_key(elements));
             }
         }
         
+        if !tab_strips_drawn {
+            draw_tab_strips(&mut frame, state, &tab_strips, &tab_titles, damage)?;
+        }
+        
         // If no windows, draw a test indicator to show compositor is working
         if state.windows.is_empty() {
-            let test_rect = Rectangle::from_loc_and_size(
-                (size.w / 2 - 100, size.h / 2 - 50),
-                (200, 100),
+            let test_rect = Rectangle::new(
+                (size.w / 2 - 100, size.h / 2 - 50).into(),
+                (200, 100).into(),
             );
-            frame.draw_solid(test_rect, &[test_rect], Color32F::new(0.2, 0.5, 0.8, 1.0))?;
-            damage_rects.push(test_rect);
+            let color = Color32F::new(0.2, 0.5, 0.8, 1.0);
+            frame.draw_solid(test_rect, &[test_rect], color)?;
+            damage.add(test_rect, color_key(color));
             info!("No windows open - rendering test indicator");
         }
         
-        // Render dock background at the bottom
-        let dock_height = state.dock.background_height;
-        let dock_y = size.h - state.dock.position_bottom - dock_height;
-        let dock_rect = Rectangle::from_loc_and_size((0, dock_y), (size.w, dock_height));
-        frame.draw_solid(dock_rect, &[dock_rect], Color32F::new(0.15, 0.15, 0.15, 0.9))?;
-        damage_rects.push(dock_rect);
+        // Render dock background at the bottom, unless a fullscreen window covers it
+        if state.dock.is_visible && state.fullscreen_window().is_none() {
+            let dock_height = state.dock.background_height;
+            let dock_y = size.h - state.dock.position_bottom - dock_height;
+            let dock_rect = Rectangle::new((0, dock_y).into(), (size.w, dock_height).into());
+            let color = Color32F::new(0.15, 0.15, 0.15, 0.9);
+            frame.draw_solid(dock_rect, &[dock_rect], color)?;
+            damage.add(dock_rect, color_key(color));
+        }
         
         // TODO: Render dock apps icons here
         // Currently skipping individual app icons - dock background visible
         
+        // The window menu goes above everything but the cursor
+        if let Some(menu) = &state.window_menu {
+            draw_window_menu(&mut frame, effects, menu, &menu_labels, damage)?;
+        }
+        
         // Render cursor as a small white square
         let cursor_x = state.pointer_pos.x as i32;
         let cursor_y = state.pointer_pos.y as i32;
         let cursor_size = 10;
         
-        let cursor_rect = Rectangle::from_loc_and_size(
-            (cursor_x - cursor_size / 2, cursor_y - cursor_size / 2),
-            (cursor_size, cursor_size),
+        let cursor_rect = Rectangle::new(
+            (cursor_x - cursor_size / 2, cursor_y - cursor_size / 2).into(),
+            (cursor_size, cursor_size).into(),
         );
         
         // Only render cursor if it's within bounds
         if cursor_rect.loc.x >= 0 && cursor_rect.loc.y >= 0 
             && (cursor_rect.loc.x + cursor_rect.size.w) <= size.w
             && (cursor_rect.loc.y + cursor_rect.size.h) <= size.h {
-            frame.draw_solid(cursor_rect, &[cursor_rect], Color32F::new(1.0, 1.0, 1.0, 1.0))?;
-            damage_rects.push(cursor_rect);
+            let color = Color32F::new(1.0, 1.0, 1.0, 1.0);
+            frame.draw_solid(cursor_rect, &[cursor_rect], color)?;
+            damage.add(cursor_rect, color_key(color));
         }
         
         // Finish frame rendering
         let _ = frame.finish();
     }
+    titles.end_frame();
     
-    // Submit the frame for display with damage information
-    backend.submit(Some(&damage_rects))?;
+    // Submit the frame for display with the areas that changed since the last one;
+    // an empty list would damage the whole window, so unchanged frames are dropped
+    let damage_rects = damage.finish();
+    if !damage_rects.is_empty() {
+        backend.submit(Some(&damage_rects))?;
+    }
     
     Ok(())
 }
 
+
+/// Draw the tabs of tabbed and stacked containers in place of the title bars
+fn draw_tab_strips(
+    frame: &mut GlesFrame<'_, '_>,
+    state: &MirageState,
+    tab_strips: &[TabStrip<WindowId>],
+    tab_titles: &HashMap<WindowId, GlesTexture>,
+    damage: &mut FrameDamage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use smithay::utils::Rectangle;
+
+    for strip in tab_strips {
+        let strip_rect = Rectangle::new(
+            (strip.rect.loc.x, strip.rect.loc.y).into(),
+            (strip.rect.size.w, strip.rect.size.h).into(),
+        );
+        let color = Color32F::new(0.15, 0.15, 0.15, 1.0);
+        frame.draw_solid(strip_rect, &[strip_rect], color)?;
+        damage.add(strip_rect, color_key(color));
+
+        for (tab, &id) in strip.tabs.iter().enumerate() {
+            let rect = strip.tab_rect(tab);
+            // Leave a 1px seam between tabs
+            let tab_rect = Rectangle::new(
+                (rect.loc.x, rect.loc.y).into(),
+                ((rect.size.w - 1).max(1), (rect.size.h - 1).max(1)).into(),
+            );
+            let color = if Some(id) == state.focused_window {
+                Color32F::new(0.2, 0.5, 0.9, 1.0) // Blue for the focused window
+            } else if tab == strip.active {
+                Color32F::new(0.3, 0.3, 0.3, 1.0) // Dark gray for the shown tab
+            } else {
+                Color32F::new(0.2, 0.2, 0.2, 1.0) // Darker gray for hidden tabs
+            };
+            frame.draw_solid(tab_rect, &[tab_rect], color)?;
+            damage.add(tab_rect, color_key(color));
+
+            if let Some(texture) = tab_titles.get(&id) {
+                let mut area = rect;
+                area.loc.x += TAB_TEXT_PADDING;
+                area.size.w -= 2 * TAB_TEXT_PADDING;
+                let alpha = if tab == strip.active { 1.0 } else { 0.6 };
+                draw_text(frame, texture, area, alpha, damage)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the window menu with its entries, highlighting the selected one
+fn draw_window_menu(
+    frame: &mut GlesFrame<'_, '_>,
+    effects: &Effects,
+    menu: &WindowMenu<WindowId>,
+    labels: &[Option<GlesTexture>],
+    damage: &mut FrameDamage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use smithay::backend::renderer::Texture;
+    use smithay::utils::Rectangle;
+
+    effects.draw_shadow(frame, menu.rect, true, damage)?;
+    let radius = (MENU_PADDING + 3) as f32;
+    effects.draw_rounded_rect(frame, menu.rect, [radius; 4], Color32F::new(0.18, 0.18, 0.2, 0.97), damage)?;
+
+    for (index, entry) in menu.entries.iter().enumerate() {
+        let rect = menu.entry_rect(index);
+        if menu.selected == Some(index) {
+            let radius = radius - MENU_PADDING as f32;
+            effects.draw_rounded_rect(frame, rect, [radius; 4], Color32F::new(0.2, 0.5, 0.9, 1.0), damage)?;
+        }
+
+        // Labels are left-aligned, so the area they are centered in is as wide as they are
+        if let Some(texture) = labels.get(index).and_then(Option::as_ref) {
+            let area = Rectangle::new(
+                (rect.loc.x + MENU_TEXT_PADDING, rect.loc.y).into(),
+                (texture.width() as i32, rect.size.h).into(),
+            );
+            draw_text(frame, texture, area, 1.0, damage)?;
+        }
+
+        if entry.checked {
+            let check = Rectangle::new(
+                (
+                    rect.loc.x + rect.size.w - MENU_TEXT_PADDING - MENU_CHECK_SIZE,
+                    rect.loc.y + (rect.size.h - MENU_CHECK_SIZE) / 2,
+                )
+                    .into(),
+                (MENU_CHECK_SIZE, MENU_CHECK_SIZE).into(),
+            );
+            effects.draw_button(frame, check, Color32F::new(1.0, 1.0, 1.0, 1.0), None, damage)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw a title texture centered in an area, cut off at its edges
+fn draw_text(
+    frame: &mut GlesFrame<'_, '_>,
+    texture: &GlesTexture,
+    area: smithay::utils::Rectangle<i32, smithay::utils::Logical>,
+    alpha: f32,
+    damage: &mut FrameDamage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use smithay::backend::renderer::{Frame, Texture};
+    use smithay::utils::{Rectangle, Transform};
+
+    let size = texture.size();
+    let pos = (
+        area.loc.x + (area.size.w - size.w) / 2,
+        area.loc.y + (area.size.h - size.h) / 2,
+    );
+    let text_rect = Rectangle::new(pos.into(), (size.w, size.h).into());
+    let clip = Rectangle::new((area.loc.x, area.loc.y).into(), (area.size.w, area.size.h).into());
+    let Some(visible) = text_rect.intersection(clip) else {
+        return Ok(());
+    };
+
+    frame.render_texture_at(texture, pos.into(), 1, 1.0, Transform::Normal, &[visible], &[], alpha)?;
+    // Textures are cached per title, so a different title comes as a different texture
+    damage.add(visible, (texture.tex_id(), pos, (size.w, size.h), alpha.to_bits()));
+    Ok(())
+}
+
+/// Key of a window's or popup's surfaces for damage tracking, changing with every commit
+fn surface_key(elements: &[WaylandSurfaceRenderElement<GlesRenderer>]) -> Vec<(Id, Option<usize>)> {
+    elements
+        .iter()
+        .map(|element| (element.id().clone(), element.current_commit().distance(Some(CommitCounter::default()))))
+        .collect()
+}
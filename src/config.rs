@@ -0,0 +1,164 @@
+//! User configuration, read from `$XDG_CONFIG_HOME/mirage-wm/mirage.conf`
+//!
+//! The file is a list of `key = value` lines. Blank lines and lines starting
+//! with `#` are ignored, unknown keys and bad values are logged and skipped.
+
+use std::path::PathBuf;
+
+use crate::decorations::WindowStyle;
+use crate::layout::{Gaps, LayoutParams};
+use crate::text::TitleFont;
+
+/// Compositor settings with their defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Initial master-stack parameters
+    pub layout: LayoutParams,
+    /// Spacing around tiled windows
+    pub gaps: Gaps,
+    /// Rounded corners and shadows of windows
+    pub window_style: WindowStyle,
+    /// Font of window titles
+    pub title_font: TitleFont,
+    /// App ids that always get Mirage's title bar, even if they ask to draw their own
+    pub force_server_decorations: Vec<String>,
+    /// Longest time between two title bar clicks that still makes a double-click, in milliseconds
+    pub double_click_interval: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            layout: LayoutParams::default(),
+            gaps: Gaps::default(),
+            window_style: WindowStyle::default(),
+            title_font: TitleFont::default(),
+            force_server_decorations: Vec::new(),
+            double_click_interval: 400,
+        }
+    }
+}
+
+impl Config {
+    /// Location of the config file, if a config directory can be determined
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("mirage-wm").join("mirage.conf"))
+    }
+
+    /// Load the config file, falling back to defaults if it doesn't exist
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                tracing::info!("Loaded config from {}", path.display());
+                Self::parse(&contents)
+            }
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Parse config file contents on top of the defaults
+    pub fn parse(contents: &str) -> Self {
+        let mut config = Self::default();
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                tracing::warn!("Config line {}: expected `key = value`", line_number + 1);
+                continue;
+            };
+
+            if !config.set(key.trim(), value.trim()) {
+                tracing::warn!("Config line {}: invalid setting `{}`", line_number + 1, line);
+            }
+        }
+
+        config
+    }
+
+    /// Apply a single setting. Returns false if the key or value is invalid.
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "master_ratio" => parse_into(value, &mut self.layout.master_ratio),
+            "master_count" => parse_into(value, &mut self.layout.master_count),
+            "inner_gap" => parse_into(value, &mut self.gaps.inner),
+            "outer_gap" => parse_into(value, &mut self.gaps.outer),
+            "smart_gaps" => parse_into(value, &mut self.gaps.smart),
+            "corner_radius" => parse_into(value, &mut self.window_style.corner_radius),
+            "shadow_size" => parse_into(value, &mut self.window_style.shadow_size),
+            "title_font" => {
+                self.title_font.family = value.to_string();
+                !value.is_empty()
+            }
+            "title_font_size" => parse_into(value, &mut self.title_font.size),
+            "force_server_decorations" => {
+                self.force_server_decorations = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|app_id| !app_id.is_empty())
+                    .map(String::from)
+                    .collect();
+                true
+            }
+            "double_click_interval" => parse_into(value, &mut self.double_click_interval),
+            _ => false,
+        }
+    }
+}
+
+fn parse_into<T: std::str::FromStr>(value: &str, target: &mut T) -> bool {
+    match value.parse() {
+        Ok(parsed) => {
+            *target = parsed;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "# layout\n\
+             master_ratio = 0.6\n\
+             master_count=2\n\
+             inner_gap = 4\n\
+             smart_gaps = false\n\
+             title_font = DejaVu Sans\n\
+             corner_radius = 6\n\
+             force_server_decorations = firefox, org.gnome.Nautilus\n\
+             double_click_interval = 250\n\
+             bogus = 1\n\
+             master_count = many\n",
+        );
+        assert_eq!(config.layout.master_ratio, 0.6);
+        assert_eq!(config.layout.master_count, 2);
+        assert_eq!(config.gaps.inner, 4);
+        assert_eq!(config.gaps.outer, Gaps::default().outer);
+        assert!(!config.gaps.smart);
+        assert_eq!(config.title_font.family, "DejaVu Sans");
+        assert_eq!(config.title_font.size, TitleFont::default().size);
+        assert_eq!(config.window_style.corner_radius, 6);
+        assert_eq!(config.force_server_decorations, ["firefox", "org.gnome.Nautilus"]);
+        assert_eq!(config.double_click_interval, 250);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(Config::parse(""), Config::default());
+    }
+}
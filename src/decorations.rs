@@ -2,8 +2,34 @@
 
 use smithay::utils::{Logical, Point, Rectangle};
 
+use crate::grab::ResizeEdges;
+
+/// Height of the server-side title bar
+pub const TITLE_BAR_HEIGHT: i32 = 32;
+
+/// Width of the band just outside a window's edges that resizes it
+pub const RESIZE_BORDER: i32 = 6;
+
+/// How window frames are drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStyle {
+    /// Radius of the rounded window corners, 0 for square corners
+    pub corner_radius: i32,
+    /// How far the drop shadow reaches beyond the window, 0 for no shadow
+    pub shadow_size: i32,
+}
+
+impl Default for WindowStyle {
+    fn default() -> Self {
+        Self {
+            corner_radius: 10,
+            shadow_size: 24,
+        }
+    }
+}
+
 /// Represents a window decoration element
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DecorationElement {
     TitleBar,
     CloseButton,
@@ -20,9 +46,18 @@ pub struct WindowDecoration {
     pub height: i32,
     pub is_focused: bool,
     pub is_maximized: bool,
+    /// Whether Mirage draws the title bar; client-side decorated windows have none
+    pub is_server_side: bool,
+    /// Whether the window covers the whole output, which hides the title bar
+    pub is_fullscreen: bool,
+    /// Whether the pointer is over the button group, which reveals the button glyphs
+    pub is_hovered: bool,
+    /// Button held down by the pointer, it acts once released over the same button
+    pub pressed: Option<DecorationElement>,
 }
 
 impl WindowDecoration {
+    /// Decorations start client-side, until the client negotiates over xdg-decoration
     pub fn new(title: String, width: i32, height: i32, is_focused: bool) -> Self {
         Self {
             title,
@@ -30,12 +65,30 @@ impl WindowDecoration {
             height,
             is_focused,
             is_maximized: false,
+            is_server_side: false,
+            is_fullscreen: false,
+            is_hovered: false,
+            pressed: None,
+        }
+    }
+
+    /// Whether Mirage draws a title bar for the window right now
+    pub fn has_title_bar(&self) -> bool {
+        self.is_server_side && !self.is_fullscreen
+    }
+
+    /// Height of the title bar, 0 when the client draws its own decorations or is fullscreen
+    pub fn title_bar_height(&self) -> i32 {
+        if self.has_title_bar() {
+            TITLE_BAR_HEIGHT
+        } else {
+            0
         }
     }
 
     /// Get the rectangle for the title bar
     pub fn title_bar_rect(&self) -> Rectangle<i32, Logical> {
-        Rectangle::from_loc_and_size((0, 0), (self.width, 32))
+        Rectangle::from_loc_and_size((0, 0), (self.width, self.title_bar_height()))
     }
 
     /// Get the rectangle for the close button
@@ -56,11 +109,38 @@ impl WindowDecoration {
         Rectangle::from_loc_and_size((right - 80, 6), (20, 20))
     }
 
-    /// Get the content area (excluding decorations)
-    pub fn content_rect(&self) -> Rectangle<i32, Logical> {
-        Rectangle::from_loc_and_size(
-            (0, 32),
-            (self.width, self.height.saturating_sub(32)),
+    /// Area of the title bar the title text is centered in, between the
+    /// left edge and the buttons
+    pub fn title_text_rect(&self) -> Rectangle<i32, Logical> {
+        let padding = 12;
+        let right = self.maximize_button_rect().loc.x - padding;
+        Rectangle::new(
+            (padding, 0).into(),
+            ((right - padding).max(0), self.title_bar_height()).into(),
+        )
+    }
+
+    /// The area covering all three buttons
+    pub fn button_group_rect(&self) -> Rectangle<i32, Logical> {
+        self.maximize_button_rect().merge(self.close_button_rect())
+    }
+
+    /// Whether a point is over the button group
+    pub fn point_on_button_group(&self, point: Point<f64, Logical>) -> bool {
+        let rect = self.button_group_rect();
+        self.has_title_bar()
+            && point.x >= rect.loc.x as f64
+            && point.x < (rect.loc.x + rect.size.w) as f64
+            && point.y >= rect.loc.y as f64
+            && point.y < (rect.loc.y + rect.size.h) as f64
+    }
+
+    /// The area left to the client surface (excluding decorations) of a window placed at `frame`
+    pub fn content_rect(&self, frame: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let title_bar = self.title_bar_height();
+        Rectangle::new(
+            (frame.loc.x, frame.loc.y + title_bar).into(),
+            (frame.size.w, (frame.size.h - title_bar).max(1)).into(),
         )
     }
 
@@ -100,32 +180,92 @@ impl WindowDecoration {
             && point.y < (rect.loc.y + rect.size.h) as f64
     }
 
+    /// The sides of the window resized from a point relative to its top-left corner.
+    /// Only the band around windows with a title bar resizes them, and corners
+    /// reach a little along the edges so they are easier to hit.
+    pub fn resize_edges_at(&self, point: Point<f64, Logical>) -> Option<ResizeEdges> {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let border = RESIZE_BORDER as f64;
+        let in_band = point.x >= -border
+            && point.x < width + border
+            && point.y >= -border
+            && point.y < height + border;
+        let on_window = point.x >= 0.0 && point.x < width && point.y >= 0.0 && point.y < height;
+        if !self.has_title_bar() || !in_band || on_window {
+            return None;
+        }
+
+        let corner = 2.0 * border;
+        let beside = point.x < 0.0 || point.x >= width;
+        let above_or_below = point.y < 0.0 || point.y >= height;
+        Some(ResizeEdges {
+            top: point.y < 0.0 || (beside && point.y < corner),
+            bottom: point.y >= height || (beside && point.y >= height - corner),
+            left: point.x < 0.0 || (above_or_below && point.x < corner),
+            right: point.x >= width || (above_or_below && point.x >= width - corner),
+        })
+    }
+
+    /// The decoration element at a point relative to the window's top-left corner
+    pub fn element_at(&self, point: Point<f64, Logical>) -> Option<DecorationElement> {
+        if !self.has_title_bar() {
+            None
+        } else if self.resize_edges_at(point).is_some() {
+            Some(DecorationElement::ResizeHandle)
+        } else if self.point_on_close_button(point) {
+            Some(DecorationElement::CloseButton)
+        } else if self.point_on_minimize_button(point) {
+            Some(DecorationElement::MinimizeButton)
+        } else if self.point_on_maximize_button(point) {
+            Some(DecorationElement::MaximizeButton)
+        } else if self.point_on_title_bar(point) {
+            Some(DecorationElement::TitleBar)
+        } else {
+            None
+        }
+    }
+
     /// Get title bar color based on focus state
     pub fn title_bar_color(&self) -> (f32, f32, f32, f32) {
         if self.is_focused {
-            (0.2, 0.2, 0.2, 1.0) // Dark gray for focused
+            (0.3, 0.3, 0.3, 1.0) // Dark gray for focused
         } else {
-            (0.15, 0.15, 0.15, 1.0) // Darker gray for unfocused
+            (0.2, 0.2, 0.2, 1.0) // Darker gray for unfocused
         }
     }
 
-    /// Get button color
+    /// Get button color, used for all buttons of unfocused windows
     pub fn button_color(&self) -> (f32, f32, f32, f32) {
-        (0.3, 0.3, 0.3, 1.0)
+        (0.45, 0.45, 0.45, 1.0)
     }
 
     /// Get close button color (red)
     pub fn close_button_color(&self) -> (f32, f32, f32, f32) {
-        (0.9, 0.2, 0.2, 1.0)
+        self.traffic_light(DecorationElement::CloseButton, (0.9, 0.2, 0.2, 1.0))
     }
 
     /// Get minimize button color (yellow)
     pub fn minimize_button_color(&self) -> (f32, f32, f32, f32) {
-        (0.9, 0.8, 0.2, 1.0)
+        self.traffic_light(DecorationElement::MinimizeButton, (0.9, 0.8, 0.2, 1.0))
     }
 
     /// Get maximize button color (green)
     pub fn maximize_button_color(&self) -> (f32, f32, f32, f32) {
-        (0.2, 0.9, 0.2, 1.0)
+        self.traffic_light(DecorationElement::MaximizeButton, (0.2, 0.9, 0.2, 1.0))
+    }
+
+    /// A button's color in its current state: grey on unfocused windows until
+    /// hovered, darkened while pressed
+    fn traffic_light(&self, button: DecorationElement, color: (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+        let (r, g, b, a) = if self.is_focused || self.is_hovered {
+            color
+        } else {
+            self.button_color()
+        };
+        if self.pressed == Some(button) {
+            (r * 0.7, g * 0.7, b * 0.7, a)
+        } else {
+            (r, g, b, a)
+        }
     }
 }
@@ -9,6 +9,16 @@ pub struct DockApp {
     pub is_running: bool,
 }
 
+impl DockApp {
+    /// Whether a window with this app id belongs to the app. The id is compared
+    /// with the launch command, also accepting reverse-DNS ids such as
+    /// `org.gnome.Nautilus` for `nautilus`.
+    pub fn matches_app_id(&self, app_id: &str) -> bool {
+        let name = app_id.rsplit('.').next().unwrap_or(app_id);
+        app_id.eq_ignore_ascii_case(&self.command) || name.eq_ignore_ascii_case(&self.command)
+    }
+}
+
 pub struct Dock {
     pub apps: Vec<DockApp>,
     pub is_visible: bool,
@@ -80,6 +90,17 @@ impl Dock {
         Rectangle::from_loc_and_size((x, y), (dock_width, self.background_height))
     }
 
+    /// Height at the bottom of the screen that windows must leave free for the dock.
+    /// A hidden dock reserves nothing.
+    pub fn exclusive_zone(&self, screen_width: i32, screen_height: i32) -> i32 {
+        if !self.is_visible {
+            return 0;
+        }
+
+        let dock_rect = self.get_dock_rect(screen_width, screen_height);
+        (screen_height - dock_rect.loc.y).max(0)
+    }
+
     pub fn get_app_rect(
         &self,
         app_index: usize,
@@ -155,6 +176,13 @@ impl Dock {
         result
     }
 
+    /// Mark the apps that have a window open, given the app ids of all open windows
+    pub fn update_running(&mut self, app_ids: &[&str]) {
+        for app in &mut self.apps {
+            app.is_running = app_ids.iter().any(|app_id| app.matches_app_id(app_id));
+        }
+    }
+
     pub fn set_app_running(&mut self, index: usize, running: bool) {
         if index < self.apps.len() {
             self.apps[index].is_running = running;
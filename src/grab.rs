@@ -0,0 +1,238 @@
+//! Interactive window moves and resizes driven by the pointer
+//!
+//! Dragging a title bar, or a client asking to be moved from its own
+//! decorations, starts a [`MoveGrab`]. While it lasts, floating windows follow
+//! the pointer; tiled windows stay in their slot until they are dropped on
+//! another tiled window and swap places with it.
+//!
+//! Dragging a window border, or a client asking to be resized, starts a
+//! [`ResizeGrab`]. Floating windows are resized one configure at a time, the
+//! next size is only sent once the client committed the previous one.
+
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::ResizeEdge;
+use smithay::utils::{Logical, Point, Serial, Size};
+
+use crate::layout::WindowGeometry;
+
+/// How far the pointer has to travel before a press on the title bar becomes a drag
+const DRAG_THRESHOLD: f64 = 4.0;
+
+/// A window being moved with the pointer; `T` identifies the window
+#[derive(Debug, Clone, Copy)]
+pub struct MoveGrab<T> {
+    pub window: T,
+    /// Button whose release ends the grab
+    pub button: u32,
+    /// Pointer position when the grab started
+    pub start: Point<f64, Logical>,
+    /// Window geometry when the grab started
+    pub initial: WindowGeometry,
+    /// Set once the pointer has moved past the drag threshold
+    pub dragging: bool,
+}
+
+impl<T> MoveGrab<T> {
+    pub fn new(window: T, button: u32, start: Point<f64, Logical>, initial: WindowGeometry) -> Self {
+        Self {
+            window,
+            button,
+            start,
+            initial,
+            dragging: false,
+        }
+    }
+
+    /// Follow the pointer to `pos`, returning where the window goes now.
+    /// `None` until the pointer has moved far enough to count as a drag.
+    pub fn motion(&mut self, pos: Point<f64, Logical>) -> Option<WindowGeometry> {
+        let (dx, dy) = (pos.x - self.start.x, pos.y - self.start.y);
+        if !self.dragging && dx.hypot(dy) < DRAG_THRESHOLD {
+            return None;
+        }
+        self.dragging = true;
+
+        let mut geom = self.initial;
+        geom.location.x += dx.round() as i32;
+        geom.location.y += dy.round() as i32;
+        Some(geom)
+    }
+}
+
+/// The sides of a window a resize moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResizeEdges {
+    pub top: bool,
+    pub bottom: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl From<ResizeEdge> for ResizeEdges {
+    fn from(edge: ResizeEdge) -> Self {
+        let (top, bottom, left, right) = match edge {
+            ResizeEdge::Top => (true, false, false, false),
+            ResizeEdge::Bottom => (false, true, false, false),
+            ResizeEdge::Left => (false, false, true, false),
+            ResizeEdge::Right => (false, false, false, true),
+            ResizeEdge::TopLeft => (true, false, true, false),
+            ResizeEdge::TopRight => (true, false, false, true),
+            ResizeEdge::BottomLeft => (false, true, true, false),
+            ResizeEdge::BottomRight => (false, true, false, true),
+            _ => (false, false, false, false),
+        };
+        Self { top, bottom, left, right }
+    }
+}
+
+/// A window being resized with the pointer; `T` identifies the window
+#[derive(Debug, Clone, Copy)]
+pub struct ResizeGrab<T> {
+    pub window: T,
+    pub edges: ResizeEdges,
+    /// Button whose release ends the grab
+    pub button: u32,
+    /// Pointer position when the grab started
+    pub start: Point<f64, Logical>,
+    /// Window geometry when the grab started
+    pub initial: WindowGeometry,
+    /// Geometry following the pointer, sent to the client as soon as it caught up
+    pub target: WindowGeometry,
+    /// Geometry sent in the configure the client hasn't committed yet, with its serial
+    pub configured: Option<(Serial, WindowGeometry)>,
+}
+
+impl<T> ResizeGrab<T> {
+    pub fn new(
+        window: T,
+        edges: ResizeEdges,
+        button: u32,
+        start: Point<f64, Logical>,
+        initial: WindowGeometry,
+    ) -> Self {
+        Self {
+            window,
+            edges,
+            button,
+            start,
+            initial,
+            target: initial,
+            configured: None,
+        }
+    }
+
+    /// Follow the pointer to `pos`, keeping the window at least `min` large.
+    /// The sides that don't move stay where they are.
+    pub fn motion(&mut self, pos: Point<f64, Logical>, min: Size<i32, Logical>) -> WindowGeometry {
+        let dx = (pos.x - self.start.x).round() as i32;
+        let dy = (pos.y - self.start.y).round() as i32;
+        let initial = self.initial;
+
+        let mut width = initial.size.w;
+        if self.edges.left {
+            width -= dx;
+        } else if self.edges.right {
+            width += dx;
+        }
+        let mut height = initial.size.h;
+        if self.edges.top {
+            height -= dy;
+        } else if self.edges.bottom {
+            height += dy;
+        }
+        let (width, height) = (width.max(min.w), height.max(min.h));
+
+        let x = if self.edges.left {
+            initial.location.x + initial.size.w - width
+        } else {
+            initial.location.x
+        };
+        let y = if self.edges.top {
+            initial.location.y + initial.size.h - height
+        } else {
+            initial.location.y
+        };
+
+        self.target = WindowGeometry::new(x, y, width, height);
+        self.target
+    }
+}
+
+/// Tracks title bar clicks to recognize double-clicks; `T` identifies a window
+#[derive(Debug)]
+pub struct ClickTracker<T> {
+    last: Option<(T, u32)>,
+}
+
+impl<T> Default for ClickTracker<T> {
+    fn default() -> Self {
+        Self { last: None }
+    }
+}
+
+impl<T: Copy + PartialEq> ClickTracker<T> {
+    /// Record a click on the window's title bar at `time` milliseconds. Returns
+    /// true if it completes a double-click, which then doesn't start another one.
+    pub fn click(&mut self, window: T, time: u32, interval: u32) -> bool {
+        match self.last.take() {
+            Some((last_window, last_time))
+                if last_window == window && time.wrapping_sub(last_time) <= interval =>
+            {
+                true
+            }
+            _ => {
+                self.last = Some((window, time));
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTN_LEFT: u32 = 0x110;
+
+    #[test]
+    fn test_move_grab_threshold() {
+        let mut grab = MoveGrab::new(1, BTN_LEFT, Point::from((100.0, 100.0)), WindowGeometry::new(50, 80, 400, 300));
+        assert!(grab.motion(Point::from((102.0, 101.0))).is_none());
+
+        let geom = grab.motion(Point::from((130.0, 90.0))).unwrap();
+        assert_eq!(geom.location, Point::from((80, 70)));
+        assert_eq!(geom.size, WindowGeometry::new(50, 80, 400, 300).size);
+
+        // Once dragging, small moves count too
+        let geom = grab.motion(Point::from((101.0, 100.0))).unwrap();
+        assert_eq!(geom.location, Point::from((51, 80)));
+    }
+
+    #[test]
+    fn test_resize_grab_anchors_opposite_side() {
+        let initial = WindowGeometry::new(100, 100, 400, 300);
+        let min = Size::from((150, 100));
+
+        let mut grab = ResizeGrab::new(1, ResizeEdge::TopLeft.into(), BTN_LEFT, Point::from((100.0, 100.0)), initial);
+        let geom = grab.motion(Point::from((150.0, 80.0)), min);
+        assert_eq!(geom.rect(), WindowGeometry::new(150, 80, 350, 320).rect());
+
+        // The bottom-right corner stays put when the minimum size stops the drag
+        let geom = grab.motion(Point::from((600.0, 600.0)), min);
+        assert_eq!(geom.rect(), WindowGeometry::new(350, 300, 150, 100).rect());
+
+        let mut grab = ResizeGrab::new(1, ResizeEdge::Right.into(), BTN_LEFT, Point::from((500.0, 200.0)), initial);
+        let geom = grab.motion(Point::from((540.0, 260.0)), min);
+        assert_eq!(geom.rect(), WindowGeometry::new(100, 100, 440, 300).rect());
+    }
+
+    #[test]
+    fn test_double_click() {
+        let mut clicks = ClickTracker::default();
+        assert!(!clicks.click(1, 1000, 400));
+        assert!(clicks.click(1, 1300, 400));
+        // A third click starts over
+        assert!(!clicks.click(1, 1500, 400));
+        assert!(!clicks.click(1, 2000, 400));
+        assert!(!clicks.click(2, 2100, 400));
+    }
+}
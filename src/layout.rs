@@ -1,5 +1,8 @@
 use smithay::utils::{Point, Size, Rectangle, Logical};
 
+use crate::scrolling::ScrollingLayout;
+use crate::tree::{ContainerTree, Direction, TabStrip};
+
 /// Information about a window's position and size
 #[derive(Debug, Clone, Copy)]
 pub struct WindowGeometry {
@@ -27,17 +30,373 @@ impl WindowGeometry {
         let rect = self.rect();
         rect.contains((x, y))
     }
+
+    /// Place a client's window geometry here, returning where the window geometry and
+    /// the client's surface end up. Clients drawing their own decorations surround the
+    /// window geometry with a shadow, so their surface starts above and left of it.
+    pub fn place_surface(
+        &self,
+        geometry: Option<Rectangle<i32, Logical>>,
+    ) -> (Rectangle<i32, Logical>, Point<i32, Logical>) {
+        match geometry {
+            Some(geometry) => (Rectangle::new(self.location, geometry.size), self.location - geometry.loc),
+            None => (self.rect(), self.location),
+        }
+    }
+
+    /// Resize to fit the client's size hints, keeping the result centered on this geometry
+    pub fn constrained(&self, hints: &SizeHints) -> WindowGeometry {
+        let width = hints.clamp_width(self.size.w);
+        let height = hints.clamp_height(self.size.h);
+        WindowGeometry::new(
+            self.location.x + (self.size.w - width) / 2,
+            self.location.y + (self.size.h - height) / 2,
+            width,
+            height,
+        )
+    }
+}
+
+/// Minimum and maximum size a client asked for; 0 leaves an axis unconstrained
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SizeHints {
+    pub min: Size<i32, Logical>,
+    pub max: Size<i32, Logical>,
+}
+
+impl SizeHints {
+    /// A window that can't be resized at all
+    pub fn is_fixed(&self) -> bool {
+        self.min.w > 0 && self.min.h > 0 && self.min == self.max
+    }
+
+    fn clamp_width(&self, width: i32) -> i32 {
+        let width = if self.max.w > 0 { width.min(self.max.w) } else { width };
+        width.max(self.min.w)
+    }
+
+    fn clamp_height(&self, height: i32) -> i32 {
+        let height = if self.max.h > 0 { height.min(self.max.h) } else { height };
+        height.max(self.min.h)
+    }
+}
+
+/// Split `length` into `count` parts and return the (offset, size) of part `index`.
+/// Any remainder is spread over the parts so they cover the full length.
+pub(crate) fn split_span(length: i32, index: usize, count: usize) -> (i32, i32) {
+    let count = count.max(1) as i64;
+    let index = index as i64;
+    let start = (length as i64 * index / count) as i32;
+    let end = (length as i64 * (index + 1) / count) as i32;
+    (start, end - start)
+}
+
+/// Like [`split_span`], but part `index` gets a share of `length` proportional to its weight
+pub(crate) fn weighted_span(length: i32, index: usize, weights: &[f32]) -> (i32, i32) {
+    let total: f64 = weights.iter().map(|&weight| weight as f64).sum();
+    if total <= 0.0 {
+        return split_span(length, index, weights.len());
+    }
+    let before: f64 = weights[..index].iter().map(|&weight| weight as f64).sum();
+    let start = (length as f64 * before / total) as i32;
+    let end = (length as f64 * (before + weights[index] as f64) / total) as i32;
+    (start, end - start)
+}
+
+/// Smallest and largest share of the area the master windows may take
+pub const MIN_MASTER_RATIO: f32 = 0.1;
+pub const MAX_MASTER_RATIO: f32 = 0.9;
+
+/// Tunable parameters shared by all tiling algorithms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutParams {
+    /// Share of the area width given to the master windows
+    pub master_ratio: f32,
+    /// Number of windows in the master area
+    pub master_count: usize,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        Self {
+            master_ratio: 0.5,
+            master_count: 1,
+        }
+    }
 }
 
-/// Simple tiling layout - splits screen into vertical tiles
+/// Spacing around tiled windows, in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gaps {
+    /// Space between neighbouring tiles
+    pub inner: i32,
+    /// Space between the tiles and the screen edge
+    pub outer: i32,
+    /// Drop all gaps while only one window is visible
+    pub smart: bool,
+}
+
+impl Default for Gaps {
+    fn default() -> Self {
+        Self {
+            inner: 8,
+            outer: 8,
+            smart: true,
+        }
+    }
+}
+
+/// Shrink a rectangle by separate amounts on its left/top and right/bottom sides.
+/// Negative amounts grow it instead. The size never drops below 1x1.
+fn inset(rect: Rectangle<i32, Logical>, low: i32, high: i32) -> Rectangle<i32, Logical> {
+    Rectangle::new(
+        (rect.loc.x + low, rect.loc.y + low).into(),
+        (
+            (rect.size.w - low - high).max(1),
+            (rect.size.h - low - high).max(1),
+        )
+            .into(),
+    )
+}
+
+/// A tiling algorithm that places windows inside an area
+pub trait Layout {
+    /// Name of the algorithm, used for logging and for selecting it
+    fn name(&self) -> &'static str;
+
+    /// Whether only a single window is visible with this many windows tiled
+    fn shows_single_window(&self, total_windows: usize) -> bool {
+        total_windows <= 1
+    }
+
+    /// Calculate geometry for the window at the given index
+    fn calculate_geometry(
+        &self,
+        area: Rectangle<i32, Logical>,
+        params: &LayoutParams,
+        window_index: usize,
+        total_windows: usize,
+    ) -> WindowGeometry;
+}
+
+/// Master window on the left, remaining windows stacked on the right
+pub struct MasterStack;
+
+impl Layout for MasterStack {
+    fn name(&self) -> &'static str {
+        "master-stack"
+    }
+
+    fn calculate_geometry(
+        &self,
+        area: Rectangle<i32, Logical>,
+        params: &LayoutParams,
+        window_index: usize,
+        total_windows: usize,
+    ) -> WindowGeometry {
+        let masters = params.master_count.min(total_windows);
+        let stacked = total_windows - masters;
+
+        // Without a stack the masters use the full width, without masters the stack does
+        let master_width = if stacked == 0 {
+            area.size.w
+        } else if masters == 0 {
+            0
+        } else {
+            (area.size.w as f32 * params.master_ratio) as i32
+        };
+
+        if window_index < masters {
+            // Master windows on the left
+            let (y, height) = split_span(area.size.h, window_index, masters);
+            WindowGeometry::new(area.loc.x, area.loc.y + y, master_width, height)
+        } else {
+            // Stack windows on the right
+            let (y, stack_height) = split_span(area.size.h, window_index - masters, stacked);
+
+            WindowGeometry::new(
+                area.loc.x + master_width,
+                area.loc.y + y,
+                area.size.w - master_width,
+                stack_height,
+            )
+        }
+    }
+}
+
+/// Every window gets an equally wide column
+pub struct Columns;
+
+impl Layout for Columns {
+    fn name(&self) -> &'static str {
+        "columns"
+    }
+
+    fn calculate_geometry(
+        &self,
+        area: Rectangle<i32, Logical>,
+        _params: &LayoutParams,
+        window_index: usize,
+        total_windows: usize,
+    ) -> WindowGeometry {
+        let (x, width) = split_span(area.size.w, window_index, total_windows);
+        WindowGeometry::new(area.loc.x + x, area.loc.y, width, area.size.h)
+    }
+}
+
+/// Windows are placed in a near-square grid, filled row by row.
+/// A partially filled last row spreads its windows over the full width.
+pub struct Grid;
+
+impl Layout for Grid {
+    fn name(&self) -> &'static str {
+        "grid"
+    }
+
+    fn calculate_geometry(
+        &self,
+        area: Rectangle<i32, Logical>,
+        _params: &LayoutParams,
+        window_index: usize,
+        total_windows: usize,
+    ) -> WindowGeometry {
+        let total = total_windows.max(1);
+        let mut columns = 1;
+        while columns * columns < total {
+            columns += 1;
+        }
+        let rows = total.div_ceil(columns);
+
+        let row = window_index / columns;
+        let column = window_index % columns;
+        let columns_in_row = if row == rows - 1 {
+            total - row * columns
+        } else {
+            columns
+        };
+
+        let (x, width) = split_span(area.size.w, column, columns_in_row);
+        let (y, height) = split_span(area.size.h, row, rows);
+        WindowGeometry::new(area.loc.x + x, area.loc.y + y, width, height)
+    }
+}
+
+/// Every window takes the full area, stacked on top of each other
+pub struct Monocle;
+
+impl Layout for Monocle {
+    fn name(&self) -> &'static str {
+        "monocle"
+    }
+
+    fn shows_single_window(&self, _total_windows: usize) -> bool {
+        true
+    }
+
+    fn calculate_geometry(
+        &self,
+        area: Rectangle<i32, Logical>,
+        _params: &LayoutParams,
+        _window_index: usize,
+        _total_windows: usize,
+    ) -> WindowGeometry {
+        WindowGeometry::new(area.loc.x, area.loc.y, area.size.w, area.size.h)
+    }
+}
+
+/// Each window takes half of the remaining space, alternating between
+/// vertical and horizontal splits so the windows spiral inwards
+pub struct Dwindle;
+
+impl Layout for Dwindle {
+    fn name(&self) -> &'static str {
+        "dwindle"
+    }
+
+    fn calculate_geometry(
+        &self,
+        area: Rectangle<i32, Logical>,
+        _params: &LayoutParams,
+        window_index: usize,
+        total_windows: usize,
+    ) -> WindowGeometry {
+        let mut rect = area;
+
+        for split in 0..window_index.min(total_windows.saturating_sub(1)) {
+            // Even splits are side by side, odd splits are top and bottom
+            if split.is_multiple_of(2) {
+                let half = rect.size.w / 2;
+                rect.loc.x += half;
+                rect.size.w -= half;
+            } else {
+                let half = rect.size.h / 2;
+                rect.loc.y += half;
+                rect.size.h -= half;
+            }
+        }
+
+        // The last window keeps whatever space is left
+        if window_index + 1 < total_windows {
+            if window_index.is_multiple_of(2) {
+                rect.size.w /= 2;
+            } else {
+                rect.size.h /= 2;
+            }
+        }
+
+        WindowGeometry::new(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h)
+    }
+}
+
+/// How tiled windows are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TilingMode {
+    /// The active [`Layout`] algorithm places windows by their index
+    Automatic,
+    /// Windows are placed by a manually arranged container tree
+    Manual,
+    /// Windows are placed in columns on a scrollable strip
+    Scrolling,
+}
+
+impl TilingMode {
+    pub fn next(self) -> Self {
+        match self {
+            TilingMode::Automatic => TilingMode::Manual,
+            TilingMode::Manual => TilingMode::Scrolling,
+            TilingMode::Scrolling => TilingMode::Automatic,
+        }
+    }
+}
+
+/// Tiling layout engine - delegates window placement to the active algorithm
 pub struct TilingLayout {
     screen_size: Size<i32, Logical>,
+    /// Space at the bottom of the screen reserved for the dock
+    reserved_bottom: i32,
+    layouts: Vec<Box<dyn Layout>>,
+    active: usize,
+    params: LayoutParams,
+    gaps: Gaps,
+    mode: TilingMode,
 }
 
 impl TilingLayout {
     pub fn new(width: i32, height: i32) -> Self {
         Self {
             screen_size: Size::from((width, height)),
+            reserved_bottom: 0,
+            layouts: vec![
+                Box::new(MasterStack),
+                Box::new(Columns),
+                Box::new(Grid),
+                Box::new(Monocle),
+                Box::new(Dwindle),
+            ],
+            active: 0,
+            params: LayoutParams::default(),
+            gaps: Gaps::default(),
+            mode: TilingMode::Automatic,
         }
     }
 
@@ -45,37 +404,217 @@ impl TilingLayout {
         self.screen_size = Size::from((width, height));
     }
 
+    pub fn screen_size(&self) -> Size<i32, Logical> {
+        self.screen_size
+    }
+
+    /// Keep the given height at the bottom of the screen free of windows
+    pub fn set_reserved_bottom(&mut self, height: i32) {
+        self.reserved_bottom = height.clamp(0, self.screen_size.h);
+    }
+
+    /// The part of the screen windows are tiled in
+    pub fn usable_area(&self) -> Rectangle<i32, Logical> {
+        Rectangle::new(
+            (0, 0).into(),
+            (self.screen_size.w, self.screen_size.h - self.reserved_bottom).into(),
+        )
+    }
+
+    /// The algorithm currently used to place windows
+    pub fn active_layout(&self) -> &dyn Layout {
+        self.layouts[self.active].as_ref()
+    }
+
+    /// Switch to the next algorithm, wrapping around at the end
+    pub fn cycle_layout(&mut self) {
+        self.active = (self.active + 1) % self.layouts.len();
+    }
+
+    pub fn params(&self) -> &LayoutParams {
+        &self.params
+    }
+
+    /// Replace the layout parameters, clamping them to sane values
+    pub fn set_params(&mut self, params: LayoutParams) {
+        self.params = LayoutParams {
+            master_ratio: params.master_ratio.clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO),
+            master_count: params.master_count,
+        };
+    }
+
+    /// Grow (positive delta) or shrink (negative delta) the master area
+    pub fn adjust_master_ratio(&mut self, delta: f32) {
+        self.set_params(LayoutParams {
+            master_ratio: self.params.master_ratio + delta,
+            ..self.params
+        });
+    }
+
+    /// Move the border between the master area and the stack to the x coordinate `position`
+    pub fn set_master_border(&mut self, position: i32, total_windows: usize) {
+        let area = self.tiling_area(self.active_layout().shows_single_window(total_windows));
+        self.set_params(LayoutParams {
+            master_ratio: (position - area.loc.x) as f32 / area.size.w.max(1) as f32,
+            ..self.params
+        });
+    }
+
+    /// Add (positive delta) or remove (negative delta) master windows
+    pub fn adjust_master_count(&mut self, delta: isize) {
+        self.set_params(LayoutParams {
+            master_count: self.params.master_count.saturating_add_signed(delta),
+            ..self.params
+        });
+    }
+
+    pub fn set_gaps(&mut self, gaps: Gaps) {
+        self.gaps = Gaps {
+            inner: gaps.inner.max(0),
+            outer: gaps.outer.max(0),
+            smart: gaps.smart,
+        };
+    }
+
+    pub fn mode(&self) -> TilingMode {
+        self.mode
+    }
+
+    /// Switch to the next tiling mode: automatic, manual, scrolling
+    pub fn cycle_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+
     /// Calculate geometry for a window at the given index
     pub fn calculate_geometry(&self, window_index: usize, total_windows: usize) -> WindowGeometry {
-        if total_windows == 0 {
-            return WindowGeometry::new(0, 0, self.screen_size.w, self.screen_size.h);
+        let layout = self.active_layout();
+        self.place(layout.shows_single_window(total_windows), |area| {
+            layout
+                .calculate_geometry(area, &self.params, window_index, total_windows)
+                .rect()
+        })
+    }
+
+    /// Calculate geometry for a window placed by a container tree.
+    /// Returns `None` if the window isn't part of the tree.
+    pub fn tree_geometry<T: Copy + PartialEq>(
+        &self,
+        tree: &ContainerTree<T>,
+        window: T,
+    ) -> Option<WindowGeometry> {
+        let single_window = tree.visible_count() <= 1;
+        self.find_geometry(single_window, window, |area| tree.arrange(area))
+    }
+
+    /// Tab strips of the tree's tabbed and stacked containers, lined up with the
+    /// tiles below them
+    pub fn tree_tab_strips<T: Copy + PartialEq>(&self, tree: &ContainerTree<T>) -> Vec<TabStrip<T>> {
+        let single_window = tree.visible_count() <= 1;
+        let mut strips = tree.tab_strips(self.tiling_area(single_window));
+
+        // Strips give up the same share of the inner gap as tiles, except at the
+        // bottom where they meet the tile of the shown window
+        if !(self.gaps.smart && single_window) {
+            let (half_low, half_high) = self.half_inner_gaps();
+            for strip in &mut strips {
+                strip.rect.loc.x += half_low;
+                strip.rect.loc.y += half_low;
+                strip.rect.size.w = (strip.rect.size.w - half_low - half_high).max(1);
+            }
         }
+        strips
+    }
+
+    /// Move the border of a window's tile in the container tree to `position`,
+    /// see [`ContainerTree::set_split_border`]
+    pub fn set_tree_split_border<T: Copy + PartialEq>(
+        &self,
+        tree: &mut ContainerTree<T>,
+        window: T,
+        direction: Direction,
+        position: i32,
+    ) -> bool {
+        let single_window = tree.visible_count() <= 1;
+        tree.set_split_border(window, direction, position, self.tiling_area(single_window))
+    }
+
+    /// Calculate the strip geometry of a window in the scrolling layout.
+    /// The result is not yet shifted by the strip's view offset.
+    /// Returns `None` if the window isn't on the strip.
+    pub fn scrolling_geometry<T: Copy + PartialEq>(
+        &self,
+        strip: &ScrollingLayout<T>,
+        window: T,
+    ) -> Option<WindowGeometry> {
+        let single_window = strip.window_count() <= 1;
+        self.find_geometry(single_window, window, |area| strip.arrange(area))
+    }
+
+    /// Scroll the strip so the window's column is fully visible
+    pub fn scroll_to<T: Copy + PartialEq>(&self, strip: &mut ScrollingLayout<T>, window: T) {
+        let single_window = strip.window_count() <= 1;
+        strip.scroll_to(window, self.tiling_area(single_window).size.w);
+    }
+
+    /// Take a window off the strip, keeping the viewport on it
+    pub fn remove_from_strip<T: Copy + PartialEq>(&self, strip: &mut ScrollingLayout<T>, window: T) -> bool {
+        // Smart gaps go by the windows left after the removal
+        let single_window = strip.window_count() <= 2;
+        strip.remove(window, self.tiling_area(single_window).size.w)
+    }
+
+    /// Place all windows with `arrange` and pick the geometry of one of them
+    fn find_geometry<T: PartialEq>(
+        &self,
+        single_window: bool,
+        window: T,
+        arrange: impl FnOnce(Rectangle<i32, Logical>) -> Vec<(T, Rectangle<i32, Logical>)>,
+    ) -> Option<WindowGeometry> {
+        let mut found = false;
+        let geom = self.place(single_window, |area| {
+            arrange(area)
+                .into_iter()
+                .find(|(candidate, _)| *candidate == window)
+                .map(|(_, rect)| {
+                    found = true;
+                    rect
+                })
+                .unwrap_or(area)
+        });
+        found.then_some(geom)
+    }
 
-        if total_windows == 1 {
-            // Single window takes full screen
-            return WindowGeometry::new(0, 0, self.screen_size.w, self.screen_size.h);
+    /// The area tiles are computed in, before each tile gives up its share of the inner gap
+    fn tiling_area(&self, single_window: bool) -> Rectangle<i32, Logical> {
+        let area = self.usable_area();
+        if self.gaps.smart && single_window {
+            return area;
         }
 
-        // Multiple windows: simple vertical split
-        // First window takes left half, others stack on right
-        let half_width = self.screen_size.w / 2;
-        
-        if window_index == 0 {
-            // Master window on left
-            WindowGeometry::new(0, 0, half_width, self.screen_size.h)
-        } else {
-            // Stack windows on right
-            let stack_height = self.screen_size.h / (total_windows - 1) as i32;
-            let stack_index = (window_index - 1) as i32;
-            let y = stack_index * stack_height;
-            
-            WindowGeometry::new(
-                half_width,
-                y,
-                self.screen_size.w - half_width,
-                stack_height,
-            )
+        // Every tile gives up half the inner gap on each side, so neighbours end up
+        // a full inner gap apart. The area is shrunk by the outer gap minus that half,
+        // which leaves exactly the outer gap at the screen edges.
+        let (half_low, half_high) = self.half_inner_gaps();
+        inset(area, self.gaps.outer - half_low, self.gaps.outer - half_high)
+    }
+
+    fn half_inner_gaps(&self) -> (i32, i32) {
+        let half_low = self.gaps.inner / 2;
+        (half_low, self.gaps.inner - half_low)
+    }
+
+    /// Apply the gaps around a tile computed by `tile` from the area it may use
+    fn place(
+        &self,
+        single_window: bool,
+        tile: impl FnOnce(Rectangle<i32, Logical>) -> Rectangle<i32, Logical>,
+    ) -> WindowGeometry {
+        let mut rect = tile(self.tiling_area(single_window));
+        if !(self.gaps.smart && single_window) {
+            let (half_low, half_high) = self.half_inner_gaps();
+            rect = inset(rect, half_low, half_high);
         }
+        WindowGeometry::new(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h)
     }
 }
 
@@ -83,9 +622,28 @@ impl TilingLayout {
 mod tests {
     use super::*;
 
+    /// A layout without gaps, so the tests only check the tiling arithmetic
+    fn gapless(width: i32, height: i32) -> TilingLayout {
+        let mut layout = TilingLayout::new(width, height);
+        layout.set_gaps(Gaps {
+            inner: 0,
+            outer: 0,
+            smart: false,
+        });
+        layout
+    }
+
+    fn layout_named(name: &str) -> TilingLayout {
+        let mut layout = gapless(1280, 800);
+        while layout.active_layout().name() != name {
+            layout.cycle_layout();
+        }
+        layout
+    }
+
     #[test]
     fn test_single_window() {
-        let layout = TilingLayout::new(1280, 800);
+        let layout = gapless(1280, 800);
         let geom = layout.calculate_geometry(0, 1);
         assert_eq!(geom.location.x, 0);
         assert_eq!(geom.location.y, 0);
@@ -93,6 +651,253 @@ mod tests {
         assert_eq!(geom.size.h, 800);
     }
 
+    #[test]
+    fn test_master_stack() {
+        let layout = gapless(1280, 800);
+        let master = layout.calculate_geometry(0, 3);
+        assert_eq!((master.location.x, master.size.w, master.size.h), (0, 640, 800));
+
+        let stack = layout.calculate_geometry(2, 3);
+        assert_eq!((stack.location.x, stack.location.y), (640, 400));
+        assert_eq!((stack.size.w, stack.size.h), (640, 400));
+    }
+
+    #[test]
+    fn test_master_ratio_and_count() {
+        let mut layout = gapless(1000, 800);
+        layout.adjust_master_ratio(0.1);
+        layout.adjust_master_count(1);
+
+        // Two masters share the left 60%, the third window gets the stack
+        let second_master = layout.calculate_geometry(1, 3);
+        assert_eq!(second_master.rect(), Rectangle::new((0, 400).into(), (600, 400).into()));
+        let stack = layout.calculate_geometry(2, 3);
+        assert_eq!(stack.rect(), Rectangle::new((600, 0).into(), (400, 800).into()));
+
+        // With no stack windows the masters use the full width
+        assert_eq!(layout.calculate_geometry(0, 2).size.w, 1000);
+
+        // Ratio is clamped and the count can't go negative
+        layout.adjust_master_ratio(5.0);
+        layout.adjust_master_count(-5);
+        assert_eq!(layout.params().master_ratio, MAX_MASTER_RATIO);
+        assert_eq!(layout.params().master_count, 0);
+        assert_eq!(layout.calculate_geometry(0, 2).size.w, 1000);
+    }
+
+    #[test]
+    fn test_set_master_border() {
+        let mut layout = gapless(1000, 800);
+        layout.set_master_border(300, 2);
+        assert_eq!(layout.calculate_geometry(1, 2).rect(), Rectangle::new((300, 0).into(), (700, 800).into()));
+
+        layout.set_master_border(-50, 2);
+        assert_eq!(layout.params().master_ratio, MIN_MASTER_RATIO);
+    }
+
+    #[test]
+    fn test_columns_cover_width() {
+        let mut layout = layout_named("columns");
+        layout.update_screen_size(1000, 800);
+        let last = layout.calculate_geometry(2, 3);
+        assert_eq!(last.location.x, 666);
+        assert_eq!(last.location.x + last.size.w, 1000);
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut layout = layout_named("grid");
+        layout.update_screen_size(1200, 800);
+        // 5 windows: 3 columns, 2 rows, last row has 2 wider windows
+        let first = layout.calculate_geometry(0, 5);
+        assert_eq!((first.size.w, first.size.h), (400, 400));
+        let last = layout.calculate_geometry(4, 5);
+        assert_eq!((last.location.x, last.location.y), (600, 400));
+        assert_eq!(last.size.w, 600);
+    }
+
+    #[test]
+    fn test_monocle() {
+        let layout = layout_named("monocle");
+        let geom = layout.calculate_geometry(3, 4);
+        assert_eq!(geom.rect(), Rectangle::new((0, 0).into(), (1280, 800).into()));
+    }
+
+    #[test]
+    fn test_dwindle() {
+        let layout = layout_named("dwindle");
+        let geoms: Vec<_> = (0..3).map(|idx| layout.calculate_geometry(idx, 3).rect()).collect();
+        assert_eq!(geoms[0], Rectangle::new((0, 0).into(), (640, 800).into()));
+        assert_eq!(geoms[1], Rectangle::new((640, 0).into(), (640, 400).into()));
+        assert_eq!(geoms[2], Rectangle::new((640, 400).into(), (640, 400).into()));
+    }
+
+    #[test]
+    fn test_cycle_layout() {
+        let mut layout = TilingLayout::new(1280, 800);
+        assert_eq!(layout.active_layout().name(), "master-stack");
+        layout.cycle_layout();
+        assert_eq!(layout.active_layout().name(), "columns");
+        for _ in 0..4 {
+            layout.cycle_layout();
+        }
+        assert_eq!(layout.active_layout().name(), "master-stack");
+    }
+
+    #[test]
+    fn test_gaps() {
+        let mut layout = TilingLayout::new(1000, 800);
+        layout.set_gaps(Gaps {
+            inner: 10,
+            outer: 20,
+            smart: false,
+        });
+
+        // Outer gap at the screen edges, inner gap between master and stack
+        let master = layout.calculate_geometry(0, 3);
+        assert_eq!(master.rect(), Rectangle::new((20, 20).into(), (475, 760).into()));
+        let top = layout.calculate_geometry(1, 3);
+        assert_eq!(top.rect(), Rectangle::new((505, 20).into(), (475, 375).into()));
+        let bottom = layout.calculate_geometry(2, 3);
+        assert_eq!(bottom.rect(), Rectangle::new((505, 405).into(), (475, 375).into()));
+        assert_eq!(bottom.location.y - (top.location.y + top.size.h), 10);
+
+        // A lone window only gets the outer gap
+        let single = layout.calculate_geometry(0, 1);
+        assert_eq!(single.rect(), Rectangle::new((20, 20).into(), (960, 760).into()));
+    }
+
+    #[test]
+    fn test_odd_inner_gap() {
+        let mut layout = layout_named("columns");
+        layout.update_screen_size(1000, 800);
+        layout.set_gaps(Gaps {
+            inner: 5,
+            outer: 0,
+            smart: false,
+        });
+        let left = layout.calculate_geometry(0, 2);
+        let right = layout.calculate_geometry(1, 2);
+        assert_eq!(left.location.x, 0);
+        assert_eq!(right.location.x - (left.location.x + left.size.w), 5);
+        assert_eq!(right.location.x + right.size.w, 1000);
+    }
+
+    #[test]
+    fn test_smart_gaps() {
+        let mut layout = TilingLayout::new(1000, 800);
+        layout.set_gaps(Gaps {
+            inner: 10,
+            outer: 20,
+            smart: true,
+        });
+        let single = layout.calculate_geometry(0, 1);
+        assert_eq!(single.rect(), Rectangle::new((0, 0).into(), (1000, 800).into()));
+        assert_eq!(layout.calculate_geometry(0, 2).location.x, 20);
+
+        // Monocle only ever shows one window
+        while layout.active_layout().name() != "monocle" {
+            layout.cycle_layout();
+        }
+        let stacked = layout.calculate_geometry(1, 3);
+        assert_eq!(stacked.rect(), Rectangle::new((0, 0).into(), (1000, 800).into()));
+    }
+
+    #[test]
+    fn test_reserved_bottom() {
+        let mut layout = gapless(1000, 800);
+        layout.set_reserved_bottom(100);
+        let single = layout.calculate_geometry(0, 1);
+        assert_eq!(single.rect(), Rectangle::new((0, 0).into(), (1000, 700).into()));
+        let stack = layout.calculate_geometry(2, 3);
+        assert_eq!(stack.location.y + stack.size.h, 700);
+
+        layout.set_reserved_bottom(0);
+        assert_eq!(layout.calculate_geometry(0, 1).size.h, 800);
+    }
+
+    #[test]
+    fn test_tree_geometry_with_gaps() {
+        let mut layout = TilingLayout::new(1000, 800);
+        layout.set_gaps(Gaps {
+            inner: 10,
+            outer: 20,
+            smart: true,
+        });
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        assert_eq!(
+            layout.tree_geometry(&tree, 1).unwrap().rect(),
+            Rectangle::new((0, 0).into(), (1000, 800).into())
+        );
+
+        tree.insert(2, Some(1));
+        let right = layout.tree_geometry(&tree, 2).unwrap();
+        assert_eq!(right.rect(), Rectangle::new((505, 20).into(), (475, 760).into()));
+        assert!(layout.tree_geometry(&tree, 3).is_none());
+    }
+
+    #[test]
+    fn test_scrolling_geometry() {
+        let mut layout = TilingLayout::new(1000, 800);
+        layout.set_gaps(Gaps {
+            inner: 10,
+            outer: 20,
+            smart: true,
+        });
+        let mut strip = ScrollingLayout::new();
+        for window in 1..=3 {
+            strip.insert(window, None);
+        }
+
+        // The tiling area is 970 wide, so each column is 485 wide including its gaps
+        let third = layout.scrolling_geometry(&strip, 3).unwrap();
+        assert_eq!(third.rect(), Rectangle::new((990, 20).into(), (475, 760).into()));
+
+        layout.scroll_to(&mut strip, 3);
+        assert_eq!(strip.view_offset(), 485);
+    }
+
+    #[test]
+    fn test_size_hints() {
+        let tile = WindowGeometry::new(0, 0, 1000, 800);
+
+        // Undersized content is centered in its tile
+        let hints = SizeHints {
+            min: Size::from((0, 0)),
+            max: Size::from((600, 0)),
+        };
+        assert_eq!(tile.constrained(&hints).rect(), Rectangle::new((200, 0).into(), (600, 800).into()));
+
+        // Minimum sizes win over the tile size
+        let hints = SizeHints {
+            min: Size::from((0, 900)),
+            max: Size::from((0, 0)),
+        };
+        assert_eq!(tile.constrained(&hints).rect(), Rectangle::new((0, -50).into(), (1000, 900).into()));
+
+        assert!(!hints.is_fixed());
+        assert!(SizeHints {
+            min: Size::from((400, 300)),
+            max: Size::from((400, 300)),
+        }
+        .is_fixed());
+        assert!(!SizeHints::default().is_fixed());
+    }
+
+    #[test]
+    fn test_place_client_side_decorated_surface() {
+        let content = WindowGeometry::new(100, 50, 800, 600);
+        // A client with a 24px shadow reports its window geometry inside its surface
+        let geometry = Rectangle::new((24, 24).into(), (800, 600).into());
+        let (window, origin) = content.place_surface(Some(geometry));
+        assert_eq!(window, content.rect());
+        assert_eq!(origin, Point::new(76, 26));
+
+        // Without a window geometry the whole surface is the window
+        assert_eq!(content.place_surface(None), (content.rect(), content.location));
+    }
+
     #[test]
     fn test_point_contains() {
         let geom = WindowGeometry::new(100, 100, 200, 200);
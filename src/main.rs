@@ -1,10 +1,19 @@
 mod backend;
 mod state;
 mod layout;
+mod tree;
+mod scrolling;
 mod decorations;
 mod wallpaper;
 mod dock;
 mod launchpad;
+mod actions;
+mod config;
+mod window;
+mod text;
+mod grab;
+mod window_menu;
+mod workspace;
 
 use backend::winit::run_winit_backend;
 use state::MirageState;
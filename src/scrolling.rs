@@ -0,0 +1,258 @@
+//! Scrollable tiling (PaperWM/niri style)
+//!
+//! Windows are placed in columns on an endless horizontal strip. The screen is
+//! a viewport onto the strip that scrolls to keep the focused column visible,
+//! so opening more windows never makes the existing ones narrower.
+
+use smithay::utils::{Logical, Rectangle};
+
+use crate::layout::split_span;
+
+/// Column widths to cycle through, as a share of the screen width
+pub const COLUMN_WIDTH_PRESETS: [f32; 3] = [1.0 / 3.0, 0.5, 2.0 / 3.0];
+
+/// Preset new columns start with
+const DEFAULT_PRESET: usize = 1;
+
+/// A column of windows stacked on top of each other
+#[derive(Debug, Clone)]
+pub struct Column<T> {
+    pub windows: Vec<T>,
+    /// Index into [`COLUMN_WIDTH_PRESETS`]
+    pub preset: usize,
+}
+
+impl<T> Column<T> {
+    fn width(&self, view_width: i32) -> i32 {
+        (view_width as f32 * COLUMN_WIDTH_PRESETS[self.preset]) as i32
+    }
+}
+
+/// Columns on a horizontal strip plus the viewport position; `T` identifies a window
+#[derive(Debug, Clone)]
+pub struct ScrollingLayout<T> {
+    columns: Vec<Column<T>>,
+    /// Strip position of the left edge of the viewport
+    view_offset: i32,
+}
+
+impl<T: Copy + PartialEq> Default for ScrollingLayout<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + PartialEq> ScrollingLayout<T> {
+    pub fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+            view_offset: 0,
+        }
+    }
+
+    /// How far the viewport is scrolled to the right
+    pub fn view_offset(&self) -> i32 {
+        self.view_offset
+    }
+
+    /// Add a window in a new column right of the focused window's column,
+    /// or at the end of the strip without a focused window
+    pub fn insert(&mut self, window: T, focused: Option<T>) {
+        let position = focused
+            .and_then(|focused| self.column_of(focused))
+            .map(|column| column + 1)
+            .unwrap_or(self.columns.len());
+        self.columns.insert(
+            position,
+            Column {
+                windows: vec![window],
+                preset: DEFAULT_PRESET,
+            },
+        );
+    }
+
+    /// Remove a window, dropping its column if it becomes empty, and keep the
+    /// viewport on the now shorter strip. Returns false if the window isn't on the strip.
+    pub fn remove(&mut self, window: T, view_width: i32) -> bool {
+        let Some(column) = self.column_of(window) else {
+            return false;
+        };
+        self.columns[column].windows.retain(|&w| w != window);
+        if self.columns[column].windows.is_empty() {
+            self.columns.remove(column);
+        }
+
+        let strip_width = self.column_x(self.columns.len(), view_width);
+        self.view_offset = self.view_offset.clamp(0, (strip_width - view_width).max(0));
+        true
+    }
+
+    /// Exchange the places of two windows on the strip.
+    /// Returns false unless both windows are on the strip.
+    pub fn swap_windows(&mut self, a: T, b: T) -> bool {
+        if a == b || self.column_of(a).is_none() || self.column_of(b).is_none() {
+            return false;
+        }
+        for window in self.columns.iter_mut().flat_map(|column| &mut column.windows) {
+            if *window == a {
+                *window = b;
+            } else if *window == b {
+                *window = a;
+            }
+        }
+        true
+    }
+
+    /// Switch the window's column to the next width preset
+    pub fn cycle_width(&mut self, window: T) {
+        if let Some(column) = self.column_of(window) {
+            let column = &mut self.columns[column];
+            column.preset = (column.preset + 1) % COLUMN_WIDTH_PRESETS.len();
+        }
+    }
+
+    /// Scroll the viewport as little as possible so the window's column is fully visible
+    pub fn scroll_to(&mut self, window: T, view_width: i32) {
+        let Some(column) = self.column_of(window) else {
+            return;
+        };
+
+        let x = self.column_x(column, view_width);
+        let width = self.columns[column].width(view_width);
+
+        if x < self.view_offset {
+            self.view_offset = x;
+        } else if x + width > self.view_offset + view_width {
+            self.view_offset = x + width - view_width;
+        }
+    }
+
+    /// Place every window on the strip. Positions are strip coordinates, with
+    /// the strip starting at the left edge of `area`; subtract the view offset
+    /// to get screen coordinates.
+    pub fn arrange(&self, area: Rectangle<i32, Logical>) -> Vec<(T, Rectangle<i32, Logical>)> {
+        let mut placed = Vec::new();
+        let mut x = area.loc.x;
+
+        for column in &self.columns {
+            let width = column.width(area.size.w);
+            let count = column.windows.len();
+            for (index, window) in column.windows.iter().enumerate() {
+                let (y, height) = split_span(area.size.h, index, count);
+                placed.push((
+                    *window,
+                    Rectangle::new((x, area.loc.y + y).into(), (width, height).into()),
+                ));
+            }
+            x += width;
+        }
+
+        placed
+    }
+
+    pub fn window_count(&self) -> usize {
+        self.columns.iter().map(|column| column.windows.len()).sum()
+    }
+
+    fn column_of(&self, window: T) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| column.windows.contains(&window))
+    }
+
+    /// Strip position of a column's left edge
+    fn column_x(&self, column: usize, view_width: i32) -> i32 {
+        self.columns[..column]
+            .iter()
+            .map(|column| column.width(view_width))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Rectangle<i32, Logical> {
+        Rectangle::new((0, 0).into(), (1200, 800).into())
+    }
+
+    #[test]
+    fn test_columns_on_strip() {
+        let mut strip = ScrollingLayout::new();
+        strip.insert(1, None);
+        strip.insert(2, Some(1));
+        strip.insert(3, Some(1));
+
+        // Half-width columns continue past the screen edge, 3 went next to 1
+        let placed = strip.arrange(area());
+        assert_eq!(placed[1], (3, Rectangle::new((600, 0).into(), (600, 800).into())));
+        assert_eq!(placed[2], (2, Rectangle::new((1200, 0).into(), (600, 800).into())));
+
+        assert!(strip.remove(3, 1200));
+        assert_eq!(strip.window_count(), 2);
+        assert_eq!(strip.arrange(area())[1].1.loc.x, 600);
+    }
+
+    #[test]
+    fn test_scroll_to_focused_column() {
+        let mut strip = ScrollingLayout::new();
+        for window in 1..=4 {
+            strip.insert(window, None);
+        }
+
+        strip.scroll_to(4, 1200);
+        assert_eq!(strip.view_offset(), 1200);
+
+        // Already visible, no scrolling
+        strip.scroll_to(3, 1200);
+        assert_eq!(strip.view_offset(), 1200);
+
+        strip.scroll_to(2, 1200);
+        assert_eq!(strip.view_offset(), 600);
+    }
+
+    #[test]
+    fn test_remove_left_of_focused_column() {
+        let mut strip = ScrollingLayout::new();
+        for window in 1..=3 {
+            strip.insert(window, None);
+            strip.cycle_width(window);
+        }
+
+        // Two-thirds columns with the last one in view
+        strip.scroll_to(3, 1200);
+        assert_eq!(strip.view_offset(), 1200);
+
+        // The viewport stays on the strip once a column left of it goes away
+        assert!(strip.remove(2, 1200));
+        assert_eq!(strip.view_offset(), 400);
+        let placed = strip.arrange(area());
+        assert_eq!(placed[1].1.loc.x - strip.view_offset(), 400);
+    }
+
+    #[test]
+    fn test_cycle_width() {
+        let mut strip = ScrollingLayout::new();
+        strip.insert(1, None);
+        strip.cycle_width(1);
+        assert_eq!(strip.arrange(area())[0].1.size.w, 800);
+        strip.cycle_width(1);
+        assert_eq!(strip.arrange(area())[0].1.size.w, 400);
+    }
+
+    #[test]
+    fn test_swap_windows() {
+        let mut strip = ScrollingLayout::new();
+        strip.insert(1, None);
+        strip.insert(2, Some(1));
+        strip.cycle_width(1);
+
+        assert!(strip.swap_windows(1, 2));
+        // Windows change places, columns keep their widths
+        let placed = strip.arrange(area());
+        assert_eq!(placed[0], (2, Rectangle::new((0, 0).into(), (800, 800).into())));
+        assert_eq!(placed[1].0, 1);
+        assert!(!strip.swap_windows(1, 3));
+    }
+}
@@ -1,51 +1,128 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use smithay::{
-    reexports::wayland_server::{DisplayHandle, protocol::wl_surface::WlSurface, Client, backend::ClientData},
+    reexports::wayland_server::{
+        DisplayHandle, protocol::{wl_output::WlOutput, wl_surface::WlSurface}, Client, Resource,
+        backend::{ClientData, ClientId, DisconnectReason},
+    },
+    reexports::wayland_protocols::xdg::{
+        shell::server::xdg_toplevel,
+        decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode as DecorationMode,
+    },
     wayland::{
-        compositor::{CompositorState, CompositorHandler, CompositorClientState},
-        shell::xdg::{XdgShellHandler, XdgShellState, ToplevelSurface},
+        compositor::{with_states, CompositorState, CompositorHandler, CompositorClientState},
+        shell::xdg::{
+            XdgShellHandler, XdgShellState, ToplevelSurface, PopupSurface, PositionerState,
+            SurfaceCachedState, XdgToplevelSurfaceData,
+        },
         output::OutputHandler,
         buffer::BufferHandler,
         shm::{ShmHandler, ShmState},
+        shell::xdg::decoration::{XdgDecorationHandler, XdgDecorationState},
+    },
+    input::{
+        Seat, SeatHandler, SeatState,
+        pointer::{Focus, PointerHandle},
+        keyboard::{KeyboardHandle, Keycode, Keysym},
     },
-    input::{SeatHandler, SeatState, pointer::PointerHandle, keyboard::KeyboardHandle},
     output::Output,
-    utils::{Point, Logical},
+    desktop::{
+        find_popup_root_surface, get_popup_toplevel_coords, PopupGrab, PopupKeyboardGrab, PopupKind,
+        PopupManager, PopupPointerGrab, PopupUngrabStrategy,
+    },
+    backend::renderer::utils::on_commit_buffer_handler,
+    utils::{Point, Logical, Rectangle, Serial, Size},
 };
-use crate::layout::TilingLayout;
-use crate::decorations::WindowDecoration;
+use crate::layout::{SizeHints, TilingLayout, TilingMode, WindowGeometry};
+use crate::scrolling::ScrollingLayout;
+use crate::tree::{ContainerTree, Direction, TabStrip};
+use crate::backend::winit::SERIAL_COUNTER;
+use crate::decorations::{DecorationElement, WindowDecoration};
 use crate::dock::Dock;
 use crate::launchpad::Launchpad;
+use crate::config::Config;
+use crate::grab::{ClickTracker, MoveGrab, ResizeEdges, ResizeGrab};
+use crate::window_menu::{MenuEntry, MenuItem, WindowMenu};
+use crate::workspace::{Workspace, WORKSPACE_COUNT};
+use crate::window::{DecorationPreference, WindowId, WindowRegistry};
+
+/// Smallest width, and height below the title bar, a resize leaves a window
+const MIN_WINDOW_SIZE: i32 = 50;
 
 /// Client state - stores per-client data
 #[derive(Debug)]
 pub struct ClientState {
     pub compositor_state: CompositorClientState,
+    /// Set once the client's connection is gone
+    pub disconnected: AtomicBool,
 }
 
 impl Default for ClientState {
     fn default() -> Self {
         Self {
             compositor_state: CompositorClientState::default(),
+            disconnected: AtomicBool::new(false),
         }
     }
 }
 
-impl ClientData for ClientState {}
+impl ClientState {
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Relaxed)
+    }
+}
+
+impl ClientData for ClientState {
+    fn disconnected(&self, client_id: ClientId, reason: DisconnectReason) {
+        tracing::info!("Client {:?} disconnected: {:?}", client_id, reason);
+        self.disconnected.store(true, Ordering::Relaxed);
+    }
+}
 
 pub struct MirageState {
     pub compositor: CompositorState,
     pub xdg_shell: XdgShellState,
     pub shm: ShmState,
+    /// Owns the xdg-decoration global, the protocol is handled through [`XdgDecorationHandler`]
+    #[allow(dead_code)]
+    pub xdg_decoration: XdgDecorationState,
+    pub config: Config,
     pub output: Option<Output>,
-    pub windows: Vec<ToplevelSurface>,
-    pub decorations: Vec<WindowDecoration>,
+    pub windows: WindowRegistry,
+    /// Floating windows from bottom to top
+    pub floating_stack: Vec<WindowId>,
+    /// Minimized windows, most recently minimized last
+    pub minimized: Vec<WindowId>,
     pub pointer_pos: Point<f64, Logical>,
-    pub focused_window: Option<usize>,
+    pub focused_window: Option<WindowId>,
     pub layout: TilingLayout,
+    /// Container tree used by manual tiling, kept in sync with the tiled windows
+    pub tree: ContainerTree<WindowId>,
+    /// Column strip used by scrolling tiling, kept in sync with the tiled windows
+    pub strip: ScrollingLayout<WindowId>,
     pub seat_state: SeatState<Self>,
     pub pointer: Option<PointerHandle<Self>>,
     pub keyboard: Option<KeyboardHandle<Self>>,
-    #[allow(dead_code)]
+    /// Keys whose press was consumed by a compositor action; their release is swallowed too
+    pub suppressed_keys: Vec<Keycode>,
+    /// Pointer buttons whose press was consumed by the compositor; their release is swallowed too
+    pub suppressed_buttons: Vec<u32>,
+    /// Window being dragged by its title bar
+    pub move_grab: Option<MoveGrab<WindowId>>,
+    /// Window being resized by its border or on the client's request
+    pub resize_grab: Option<ResizeGrab<WindowId>>,
+    /// Popups of all windows, found through their parent surface
+    pub popups: PopupManager,
+    /// Menu popup holding the pointer and keyboard until it is dismissed
+    pub popup_grab: Option<PopupGrab<Self>>,
+    pub title_bar_clicks: ClickTracker<WindowId>,
+    /// Context menu opened from a title bar
+    pub window_menu: Option<WindowMenu<WindowId>>,
+    /// Index of the shown workspace
+    pub active_workspace: usize,
+    /// Tiling state of every workspace; the slot of the shown one is unused
+    /// while its state lives in `tree` and `strip`
+    pub workspaces: Vec<Workspace>,
     pub dock: Dock,
     #[allow(dead_code)]
     pub launchpad: Launchpad,
@@ -56,24 +133,47 @@ impl MirageState {
         let compositor = CompositorState::new::<Self>(display_handle);
         let xdg_shell = XdgShellState::new::<Self>(display_handle);
         let shm = ShmState::new::<Self>(display_handle, vec![]);
+        let xdg_decoration = XdgDecorationState::new::<Self>(display_handle);
         let seat_state = SeatState::new();
+        let config = Config::load();
 
-        Self { 
+        let mut layout = TilingLayout::new(1280, 800);
+        layout.set_params(config.layout);
+        layout.set_gaps(config.gaps);
+
+        let mut state = Self { 
             compositor, 
             xdg_shell,
             shm,
+            xdg_decoration,
+            config,
             output: None,
-            windows: Vec::new(),
-            decorations: Vec::new(),
+            windows: WindowRegistry::new(),
+            floating_stack: Vec::new(),
+            minimized: Vec::new(),
             pointer_pos: Point::from((0.0, 0.0)),
             focused_window: None,
-            layout: TilingLayout::new(1280, 800),
+            layout,
+            tree: ContainerTree::new(),
+            strip: ScrollingLayout::new(),
             seat_state,
             pointer: None,
             keyboard: None,
+            suppressed_keys: Vec::new(),
+            suppressed_buttons: Vec::new(),
+            move_grab: None,
+            resize_grab: None,
+            popups: PopupManager::default(),
+            popup_grab: None,
+            title_bar_clicks: ClickTracker::default(),
+            window_menu: None,
+            active_workspace: 0,
+            workspaces: (0..WORKSPACE_COUNT).map(|_| Workspace::default()).collect(),
             dock: Dock::new(),
             launchpad: Launchpad::new(),
-        }
+        };
+        state.update_usable_area();
+        state
     }
 
     pub fn initialize_seat(&mut self, display_handle: &DisplayHandle) {
@@ -86,28 +186,1166 @@ impl MirageState {
         self.keyboard = seat.add_keyboard(Default::default(), 200, 200).ok();
     }
 
-    /// Find which window index is at the given position
-    pub fn window_at(&self, pos: Point<f64, Logical>) -> Option<usize> {
+    /// Find which window is at the given position
+    pub fn window_at(&self, pos: Point<f64, Logical>) -> Option<WindowId> {
         // Windows are stacked - iterate in reverse to find topmost window
-        for idx in (0..self.windows.len()).rev() {
-            let geom = self.layout.calculate_geometry(idx, self.windows.len());
+        self.stacking_order()
+            .into_iter()
+            .rev()
+            .find(|&id| self.constrained_geometry(id).contains_point(pos))
+    }
+
+    /// Windows placed by the tiling layout on the shown workspace, in tiling order
+    pub fn tiled_windows(&self) -> Vec<WindowId> {
+        self.windows
+            .iter()
+            .filter(|window| !window.is_floating() && !self.is_minimized(window.id))
+            .filter(|window| window.workspace == self.active_workspace)
+            .map(|window| window.id)
+            .collect()
+    }
+
+    pub fn is_on_active_workspace(&self, id: WindowId) -> bool {
+        self.windows
+            .get(id)
+            .is_some_and(|window| window.workspace == self.active_workspace)
+    }
+
+    pub fn is_always_on_top(&self, id: WindowId) -> bool {
+        self.windows.get(id).is_some_and(|window| window.always_on_top)
+    }
+
+    pub fn is_minimized(&self, id: WindowId) -> bool {
+        self.minimized.contains(&id)
+    }
+
+    pub fn is_maximized(&self, id: WindowId) -> bool {
+        self.windows.get(id).is_some_and(|window| window.decoration.is_maximized)
+    }
+
+    pub fn is_fullscreen(&self, id: WindowId) -> bool {
+        self.windows.get(id).is_some_and(|window| window.decoration.is_fullscreen)
+    }
+
+    /// The fullscreen window on the shown workspace
+    pub fn fullscreen_window(&self) -> Option<WindowId> {
+        self.stacking_order()
+            .into_iter()
+            .rev()
+            .find(|&id| self.is_fullscreen(id))
+    }
+
+    pub fn is_floating(&self, id: WindowId) -> bool {
+        self.windows.get(id).is_some_and(|window| window.is_floating())
+    }
+
+    /// Whether the window is shown, rather than minimized, on another workspace or hidden behind a tab
+    pub fn is_window_visible(&self, id: WindowId) -> bool {
+        if self.is_minimized(id) || !self.is_on_active_workspace(id) {
+            return false;
+        }
+        self.is_floating(id) || self.layout.mode() != TilingMode::Manual || !self.tree.is_hidden(id)
+    }
+
+    /// All visible windows from bottom to top: tiled windows first with maximized
+    /// ones covering the rest, floating windows above them, then windows kept on
+    /// top and finally fullscreen windows
+    pub fn stacking_order(&self) -> Vec<WindowId> {
+        let mut order = self.tiled_windows();
+        order.retain(|&id| self.is_window_visible(id));
+        order.sort_by_key(|&id| self.is_maximized(id));
+        order.extend(
+            self.floating_stack
+                .iter()
+                .copied()
+                .filter(|&id| self.is_on_active_workspace(id)),
+        );
+        order.sort_by_key(|&id| (self.is_fullscreen(id), self.is_always_on_top(id)));
+        order
+    }
+
+    /// The button of the topmost window's decoration under the given position
+    pub fn decoration_button_at(&self, pos: Point<f64, Logical>) -> Option<(WindowId, DecorationElement)> {
+        let id = self.window_at(pos)?;
+        let window = self.windows.get(id)?;
+        let geom = self.constrained_geometry(id);
+        let local = Point::from((pos.x - geom.location.x as f64, pos.y - geom.location.y as f64));
+        match window.decoration.element_at(local)? {
+            DecorationElement::TitleBar | DecorationElement::ResizeHandle => None,
+            button => Some((id, button)),
+        }
+    }
+
+    /// Tab strips of the tabbed and stacked containers on screen, titled after their windows
+    pub fn tab_strips(&self) -> Vec<TabStrip<WindowId>> {
+        if self.layout.mode() != TilingMode::Manual {
+            return Vec::new();
+        }
+        let mut strips = self.layout.tree_tab_strips(&self.tree);
+        for strip in &mut strips {
+            strip.titles = strip
+                .tabs
+                .iter()
+                .map(|&id| {
+                    self.windows
+                        .get(id)
+                        .map(|window| window.decoration.title.clone())
+                        .unwrap_or_default()
+                })
+                .collect();
+        }
+        strips
+    }
+
+    /// The window of the tab under the given position, unless a floating or maximized window covers it
+    pub fn tab_at(&self, pos: Point<f64, Logical>) -> Option<WindowId> {
+        if self.window_at(pos).is_some_and(|id| self.is_floating(id) || self.is_maximized(id)) {
+            return None;
+        }
+        self.tab_strips().iter().find_map(|strip| strip.tab_at(pos))
+    }
+
+    /// Reveal the button glyphs of the window whose buttons are under the pointer
+    pub fn update_decoration_hover(&mut self, pos: Point<f64, Logical>) {
+        let hovered = self.window_at(pos).filter(|&id| {
+            let origin = self.constrained_geometry(id).location;
+            let local = Point::from((pos.x - origin.x as f64, pos.y - origin.y as f64));
+            self.windows
+                .get(id)
+                .is_some_and(|window| window.decoration.point_on_button_group(local))
+        });
+
+        for window in self.windows.iter_mut() {
+            window.decoration.is_hovered = Some(window.id) == hovered;
+        }
+    }
+
+    /// Hold down a title bar button until the pointer button is released
+    pub fn press_decoration_button(&mut self, id: WindowId, button: DecorationElement) {
+        if let Some(window) = self.windows.get_mut(id) {
+            window.decoration.pressed = Some(button);
+        }
+    }
+
+    /// Let go of the held title bar button. Returns it if the pointer is still
+    /// over it, so its action should run.
+    pub fn release_decoration_button(&mut self, pos: Point<f64, Logical>) -> Option<(WindowId, DecorationElement)> {
+        let window = self.windows.iter_mut().find(|window| window.decoration.pressed.is_some())?;
+        let (id, button) = (window.id, window.decoration.pressed.take()?);
+        (self.decoration_button_at(pos) == Some((id, button))).then_some((id, button))
+    }
+
+    /// The window whose title bar, away from the buttons, is under the given position
+    pub fn title_bar_at(&self, pos: Point<f64, Logical>) -> Option<WindowId> {
+        let id = self.window_at(pos)?;
+        let window = self.windows.get(id)?;
+        let origin = self.constrained_geometry(id).location;
+        let local = Point::from((pos.x - origin.x as f64, pos.y - origin.y as f64));
+        (window.decoration.element_at(local)? == DecorationElement::TitleBar).then_some(id)
+    }
+
+    /// Handle a `button` click on a window's title bar at `time` milliseconds:
+    /// a double-click toggles maximize, any other click may start a move
+    pub fn click_title_bar(&mut self, id: WindowId, button: u32, time: u32) {
+        self.set_focus(Some(id));
+        if self.title_bar_clicks.click(id, time, self.config.double_click_interval) {
+            self.toggle_maximized(id);
+            return;
+        }
+        self.begin_move(id, button);
+    }
+
+    /// Start moving a window with the pointer until `button` is released.
+    /// Maximized and fullscreen windows stay where they are.
+    pub fn begin_move(&mut self, id: WindowId, button: u32) {
+        if self.is_maximized(id) || self.is_fullscreen(id) {
+            return;
+        }
+        self.move_grab = Some(MoveGrab::new(id, button, self.pointer_pos, self.window_geometry(id)));
+        tracing::info!("Moving window {}", id);
+    }
+
+    /// Where a window's client area is on screen and where its surface starts, with
+    /// the client's window geometry placed in the content area
+    pub fn surface_placement(&self, id: WindowId) -> Option<(Rectangle<i32, Logical>, Point<i32, Logical>)> {
+        let window = self.windows.get(id)?;
+        let geometry = with_states(window.surface.wl_surface(), |states| {
+            states.cached_state.get::<SurfaceCachedState>().current().geometry
+        });
+        Some(self.content_geometry(id).place_surface(geometry))
+    }
+
+    /// Where the window geometry of a window's surface starts on screen, which is what
+    /// its popups are positioned against
+    fn popup_origin(&self, id: WindowId) -> Option<Point<i32, Logical>> {
+        self.surface_placement(id).map(|(area, _)| area.loc)
+    }
+
+    /// Popups of a window with the screen position of their surfaces, topmost first
+    pub fn window_popups(&self, id: WindowId) -> Vec<(PopupKind, Point<i32, Logical>)> {
+        let (Some(window), Some(origin)) = (self.windows.get(id), self.popup_origin(id)) else {
+            return Vec::new();
+        };
+        PopupManager::popups_for_surface(window.surface.wl_surface())
+            .map(|(popup, offset)| {
+                let location = origin + offset - popup.geometry().loc;
+                (popup, location)
+            })
+            .collect()
+    }
+
+    /// The popup under the given position with the screen position of its surface.
+    /// Popups lie above their parent window but below the windows stacked over it.
+    pub fn popup_at(&self, pos: Point<f64, Logical>) -> Option<(PopupKind, Point<i32, Logical>)> {
+        let window = self.window_at(pos);
+        for id in self.stacking_order().into_iter().rev() {
+            let popup = self.window_popups(id).into_iter().find(|(popup, location)| {
+                let mut rect = popup.geometry();
+                rect.loc += *location;
+                rect.to_f64().contains(pos)
+            });
+            if popup.is_some() {
+                return popup;
+            }
+            if Some(id) == window {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Whether a menu popup holds the pointer; any click outside its client dismisses it
+    pub fn popup_grab_active(&self) -> bool {
+        self.popup_grab.as_ref().is_some_and(|grab| !grab.has_ended())
+    }
+
+    /// Move a popup back inside the output where its positioner allows, by sliding,
+    /// flipping or resizing it
+    fn unconstrain_popup(&self, popup: &PopupSurface) {
+        let kind = PopupKind::Xdg(popup.clone());
+        let Ok(root) = find_popup_root_surface(&kind) else {
+            return;
+        };
+        let Some(origin) = self.windows.find_by_surface(&root).and_then(|id| self.popup_origin(id)) else {
+            return;
+        };
+        // The positioner works relative to the popup's parent
+        let mut target = Rectangle::from_size(self.layout.screen_size());
+        target.loc -= origin + get_popup_toplevel_coords(&kind);
+        popup.with_pending_state(|state| {
+            state.geometry = state.positioner.get_unconstrained_geometry(target);
+        });
+    }
+
+    /// The window whose resize border is under the given position, with the sides it resizes
+    pub fn resize_edges_at(&self, pos: Point<f64, Logical>) -> Option<(WindowId, ResizeEdges)> {
+        for id in self.stacking_order().into_iter().rev() {
+            let geom = self.constrained_geometry(id);
+            // Borders lie outside their window, a window covering the position wins
             if geom.contains_point(pos) {
-                return Some(idx);
+                return None;
+            }
+            if self.is_maximized(id) || self.is_fullscreen(id) {
+                continue;
+            }
+            let Some(window) = self.windows.get(id) else {
+                continue;
+            };
+            let local = Point::from((pos.x - geom.location.x as f64, pos.y - geom.location.y as f64));
+            if window.decoration.element_at(local) == Some(DecorationElement::ResizeHandle) {
+                return Some((id, window.decoration.resize_edges_at(local)?));
             }
         }
         None
     }
 
-    /// Set focus to a specific window
-    pub fn set_focus(&mut self, idx: Option<usize>) {
-        if let Some(idx) = idx {
-            if idx < self.windows.len() {
-                self.focused_window = Some(idx);
-                tracing::info!("Window focus changed to {}", idx);
+    /// Start resizing a window with the pointer until `button` is released.
+    /// Maximized and fullscreen windows keep their size.
+    pub fn begin_resize(&mut self, id: WindowId, edges: ResizeEdges, button: u32) {
+        if self.is_maximized(id) || self.is_fullscreen(id) || edges == ResizeEdges::default() {
+            return;
+        }
+        self.resize_grab = Some(ResizeGrab::new(id, edges, button, self.pointer_pos, self.window_geometry(id)));
+        self.set_resizing_state(id, true);
+        tracing::info!("Resizing window {}", id);
+    }
+
+    /// Resize the window being resized to follow the pointer
+    pub fn update_resize(&mut self, pos: Point<f64, Logical>) {
+        let Some(id) = self.resize_grab.map(|grab| grab.window) else {
+            return;
+        };
+        let Some(window) = self.windows.get(id) else {
+            return;
+        };
+        let title_bar = window.decoration.title_bar_height();
+        let min = Size::from((
+            window.size_hints.min.w.max(MIN_WINDOW_SIZE),
+            window.size_hints.min.h.max(MIN_WINDOW_SIZE) + title_bar,
+        ));
+
+        let Some(grab) = self.resize_grab.as_mut() else {
+            return;
+        };
+        grab.motion(pos, min);
+        let edges = grab.edges;
+
+        if self.is_floating(id) {
+            self.send_resize_configure(id);
+        } else {
+            self.resize_tile(id, edges, pos);
+        }
+    }
+
+    /// Finish a resize
+    pub fn end_resize(&mut self) {
+        let Some(grab) = self.resize_grab.take() else {
+            return;
+        };
+        let id = grab.window;
+        // The final size is taken right away rather than after the client caught up
+        if let Some(floating) = self.windows.get_mut(id).and_then(|window| window.floating.as_mut()) {
+            *floating = grab.target;
+        }
+        self.set_resizing_state(id, false);
+        self.reconfigure_windows();
+    }
+
+    /// Configure a floating window being resized with the size the pointer asks for,
+    /// unless the client has yet to commit the previous one
+    fn send_resize_configure(&mut self, id: WindowId) {
+        let shown = self.window_geometry(id);
+        let Some(grab) = self.resize_grab.as_mut().filter(|grab| grab.window == id) else {
+            return;
+        };
+        if grab.configured.is_some() {
+            return;
+        }
+        if grab.target.rect() != shown.rect() {
+            // The serial is filled in once the configure goes out
+            grab.configured = Some((Serial::from(0), grab.target));
+        }
+
+        let serial = self.configure_window(id);
+        if let Some(grab) = self.resize_grab.as_mut() {
+            match (serial, grab.configured.as_mut()) {
+                (Some(serial), Some((pending, _))) => *pending = serial,
+                (None, Some(_)) => grab.configured = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Move a floating window being resized to the geometry the client just committed a
+    /// buffer for, then send the size the pointer moved to in the meantime
+    fn resize_committed(&mut self, id: WindowId) {
+        let Some((serial, geom)) = self
+            .resize_grab
+            .filter(|grab| grab.window == id)
+            .and_then(|grab| grab.configured)
+        else {
+            return;
+        };
+        let Some(window) = self.windows.get_mut(id) else {
+            return;
+        };
+        let committed = with_states(window.surface.wl_surface(), |states| {
+            states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .and_then(|data| data.lock().unwrap().current_serial)
+        });
+        if !committed.is_some_and(|committed| committed.is_no_older_than(&serial)) {
+            return;
+        }
+
+        if let Some(floating) = window.floating.as_mut() {
+            *floating = geom;
+        }
+        if let Some(grab) = self.resize_grab.as_mut() {
+            grab.configured = None;
+        }
+        self.send_resize_configure(id);
+    }
+
+    /// Move the borders of a tiled window being resized to the pointer. In manual
+    /// tiling the neighbouring tiles give up or gain the space; in automatic
+    /// tiling the master-stack layout moves the border between its two areas.
+    fn resize_tile(&mut self, id: WindowId, edges: ResizeEdges, pos: Point<f64, Logical>) {
+        let (x, y) = (pos.x.round() as i32, pos.y.round() as i32);
+        match self.layout.mode() {
+            TilingMode::Automatic => {
+                if (edges.left || edges.right) && self.layout.active_layout().name() == "master-stack" {
+                    let count = self.tiled_windows().len();
+                    self.layout.set_master_border(x, count);
+                }
+            }
+            TilingMode::Manual => {
+                if edges.left {
+                    self.layout.set_tree_split_border(&mut self.tree, id, Direction::Left, x);
+                } else if edges.right {
+                    self.layout.set_tree_split_border(&mut self.tree, id, Direction::Right, x);
+                }
+                if edges.top {
+                    self.layout.set_tree_split_border(&mut self.tree, id, Direction::Up, y);
+                } else if edges.bottom {
+                    self.layout.set_tree_split_border(&mut self.tree, id, Direction::Down, y);
+                }
+            }
+            // Columns come in preset widths and share their height equally
+            TilingMode::Scrolling => {}
+        }
+        self.reconfigure_windows();
+    }
+
+    /// Tell the client whether it is being interactively resized
+    fn set_resizing_state(&mut self, id: WindowId, resizing: bool) {
+        if let Some(window) = self.windows.get(id) {
+            window.surface.with_pending_state(|state| {
+                if resizing {
+                    state.states.set(xdg_toplevel::State::Resizing);
+                } else {
+                    state.states.unset(xdg_toplevel::State::Resizing);
+                }
+            });
+        }
+        self.configure_window(id);
+    }
+
+    /// The button of the pointer press with the given serial if it is still held on the surface,
+    /// which clients must show before they may start a move or resize
+    fn pointer_grab_button(&self, surface: &WlSurface, serial: smithay::utils::Serial) -> Option<u32> {
+        let pointer = self.pointer.as_ref()?;
+        if !pointer.has_grab(serial) {
+            return None;
+        }
+        let start = pointer.grab_start_data()?;
+        let (focus, _) = start.focus?;
+        focus.id().same_client_as(&surface.id()).then_some(start.button)
+    }
+
+    /// Let a floating window being dragged follow the pointer
+    pub fn update_move(&mut self, pos: Point<f64, Logical>) {
+        let Some(grab) = self.move_grab.as_mut() else {
+            return;
+        };
+        let Some(geom) = grab.motion(pos) else {
+            return;
+        };
+        if let Some(floating) = self.windows.get_mut(grab.window).and_then(|window| window.floating.as_mut()) {
+            *floating = geom;
+        }
+    }
+
+    /// Finish a move. A tiled window dropped on another tiled window swaps places with it.
+    pub fn end_move(&mut self, pos: Point<f64, Logical>) {
+        let Some(grab) = self.move_grab.take() else {
+            return;
+        };
+        if !grab.dragging || self.is_floating(grab.window) {
+            return;
+        }
+        let target = self
+            .window_at(pos)
+            .filter(|&id| id != grab.window && !self.is_floating(id) && !self.is_maximized(id));
+        if let Some(target) = target {
+            self.swap_tiled_windows(grab.window, target);
+        }
+    }
+
+    /// Exchange the slots of two tiled windows in every tiling mode
+    pub fn swap_tiled_windows(&mut self, a: WindowId, b: WindowId) {
+        self.windows.swap(a, b);
+        self.tree.swap_windows(a, b);
+        self.strip.swap_windows(a, b);
+        tracing::info!("Swapped windows {} and {}", a, b);
+        self.reconfigure_windows();
+    }
+
+    /// Where the window is placed on screen
+    pub fn window_geometry(&self, id: WindowId) -> WindowGeometry {
+        // Fullscreen windows cover the whole output, dock included
+        if self.is_fullscreen(id) {
+            let screen = self.layout.screen_size();
+            return WindowGeometry::new(0, 0, screen.w, screen.h);
+        }
+        if self.is_maximized(id) {
+            let area = self.layout.usable_area();
+            return WindowGeometry::new(area.loc.x, area.loc.y, area.size.w, area.size.h);
+        }
+        if let Some(geom) = self.windows.get(id).and_then(|window| window.floating) {
+            return geom;
+        }
+
+        match self.layout.mode() {
+            TilingMode::Manual => {
+                if let Some(geom) = self.layout.tree_geometry(&self.tree, id) {
+                    return geom;
+                }
+            }
+            TilingMode::Scrolling => {
+                if let Some(mut geom) = self.layout.scrolling_geometry(&self.strip, id) {
+                    // Translate from the strip to the viewport
+                    geom.location.x -= self.strip.view_offset();
+                    return geom;
+                }
+            }
+            TilingMode::Automatic => {}
+        }
+
+        let tiled = self.tiled_windows();
+        let slot = tiled.iter().position(|&tiled_id| tiled_id == id).unwrap_or(0);
+        self.layout.calculate_geometry(slot, tiled.len())
+    }
+
+    /// Where the client's surface goes: the window's place below the title bar,
+    /// shrunk or grown to respect the size hints of tiled windows
+    pub fn content_geometry(&self, id: WindowId) -> WindowGeometry {
+        let frame = self.window_geometry(id);
+        let Some(window) = self.windows.get(id) else {
+            return frame;
+        };
+        let rect = window.decoration.content_rect(frame.rect());
+        let content = WindowGeometry::new(rect.loc.x, rect.loc.y, rect.size.w, rect.size.h);
+        if window.is_floating() || window.decoration.is_fullscreen {
+            content
+        } else {
+            content.constrained(&window.size_hints)
+        }
+    }
+
+    /// Where the window is placed on screen including its title bar, fitted around
+    /// the content so size hints move the title bar along with the surface
+    pub fn constrained_geometry(&self, id: WindowId) -> WindowGeometry {
+        let content = self.content_geometry(id);
+        let title_bar = self.title_bar_height(id);
+        WindowGeometry::new(
+            content.location.x,
+            content.location.y - title_bar,
+            content.size.w,
+            content.size.h + title_bar,
+        )
+    }
+
+    /// Height of the window's server-side title bar, 0 for client-side decorations
+    pub fn title_bar_height(&self, id: WindowId) -> i32 {
+        self.windows
+            .get(id)
+            .map_or(0, |window| window.decoration.title_bar_height())
+    }
+
+    /// The client surface under the given position and where that surface's origin is,
+    /// `None` over title bars and empty space
+    pub fn surface_under(&self, pos: Point<f64, Logical>) -> Option<(WlSurface, Point<f64, Logical>)> {
+        if let Some((popup, location)) = self.popup_at(pos) {
+            return Some((popup.wl_surface().clone(), location.to_f64()));
+        }
+        let id = self.window_at(pos)?;
+        let (area, origin) = self.surface_placement(id)?;
+        if !area.to_f64().contains(pos) {
+            return None;
+        }
+        let window = self.windows.get(id)?;
+        Some((window.surface.wl_surface().clone(), origin.to_f64()))
+    }
+
+    /// Move a floating window to the top of the floating stack
+    pub fn raise_window(&mut self, id: WindowId) {
+        if let Some(pos) = self.floating_stack.iter().position(|&i| i == id) {
+            self.floating_stack.remove(pos);
+            self.floating_stack.push(id);
+        }
+    }
+
+    /// Float a tiled window in place, or return a floating window to the layout
+    pub fn toggle_floating(&mut self, id: WindowId) {
+        let Some(window) = self.windows.get_mut(id) else {
+            return;
+        };
+
+        if window.floating.take().is_some() {
+            self.floating_stack.retain(|&i| i != id);
+            self.tree.insert(id, None);
+            self.strip.insert(id, None);
+            tracing::info!("Window {} is now tiled", id);
+        } else {
+            // Keep the window where it is, it only stops following the layout
+            let geom = self.constrained_geometry(id);
+            self.float_window(id, geom);
+        }
+
+        self.reconfigure_windows();
+    }
+
+    /// Take a window out of the tiling layout and place it above the tiled windows
+    fn float_window(&mut self, id: WindowId, geom: WindowGeometry) {
+        if let Some(window) = self.windows.get_mut(id) {
+            window.floating = Some(geom);
+        }
+        self.floating_stack.push(id);
+        self.tree.remove(id);
+        self.layout.remove_from_strip(&mut self.strip, id);
+        tracing::info!("Window {} is now floating", id);
+    }
+
+    /// Pick up changed min/max size hints after a commit. Windows that can't be
+    /// resized are floated in the middle of the usable area instead of stretched.
+    fn update_size_hints(&mut self, id: WindowId) {
+        let Some(window) = self.windows.get_mut(id) else {
+            return;
+        };
+        let hints = with_states(window.surface.wl_surface(), |states| {
+            let mut cached = states.cached_state.get::<SurfaceCachedState>();
+            let current = cached.current();
+            SizeHints {
+                min: current.min_size,
+                max: current.max_size,
             }
+        });
+
+        if window.size_hints == hints {
+            return;
+        }
+        let was_fixed = window.size_hints.is_fixed();
+        window.size_hints = hints;
+        tracing::info!("Window {} size hints: min {:?}, max {:?}", id, hints.min, hints.max);
+
+        if hints.is_fixed() && !was_fixed && !window.is_floating() {
+            let area = self.layout.usable_area();
+            // The hints size the surface, the title bar comes on top
+            let height = hints.min.h + window.decoration.title_bar_height();
+            let geom = WindowGeometry::new(
+                area.loc.x + (area.size.w - hints.min.w) / 2,
+                area.loc.y + (area.size.h - height) / 2,
+                hints.min.w,
+                height,
+            );
+            self.float_window(id, geom);
+        }
+
+        self.reconfigure_windows();
+    }
+
+    /// Hide a window and take it out of the layout until it is restored
+    pub fn minimize_window(&mut self, id: WindowId) {
+        if self.windows.get(id).is_none() || self.is_minimized(id) {
+            return;
+        }
+        self.floating_stack.retain(|&i| i != id);
+        self.tree.remove(id);
+        self.layout.remove_from_strip(&mut self.strip, id);
+        self.minimized.push(id);
+        tracing::info!("Window {} minimized", id);
+
+        if self.focused_window == Some(id) {
+            self.focused_window = None;
+            self.set_focus(self.stacking_order().last().copied());
+        }
+
+        self.reconfigure_windows();
+    }
+
+    /// Bring back the most recently minimized window where it was: above the
+    /// other windows if it floated, next to the focused window if it was tiled
+    pub fn restore_minimized(&mut self) {
+        let Some(id) = self.minimized.pop() else {
+            return;
+        };
+
+        // The window comes back on the shown workspace
+        if let Some(window) = self.windows.get_mut(id) {
+            window.workspace = self.active_workspace;
+        }
+        if self.is_floating(id) {
+            self.floating_stack.push(id);
         } else {
+            let focused = self.focused_tiled_window();
+            self.tree.insert(id, focused);
+            self.strip.insert(id, focused);
+        }
+        tracing::info!("Window {} restored", id);
+
+        self.set_focus(Some(id));
+        self.reconfigure_windows();
+    }
+
+    /// Let a window cover the whole output above everything else, or return it
+    /// to its tile or floating place. Without an output from the client, the
+    /// window goes fullscreen on Mirage's. Returns false if nothing changed.
+    pub fn set_fullscreen(&mut self, id: WindowId, fullscreen: bool, output: Option<WlOutput>) -> bool {
+        let Some(window) = self.windows.get(id) else {
+            return false;
+        };
+        if window.decoration.is_fullscreen == fullscreen {
+            return false;
+        }
+        let output = output.or_else(|| {
+            let client = window.surface.wl_surface().client()?;
+            self.output.as_ref()?.client_outputs(&client).next()
+        });
+
+        let Some(window) = self.windows.get_mut(id) else {
+            return false;
+        };
+        window.decoration.is_fullscreen = fullscreen;
+        window.surface.with_pending_state(|state| {
+            if fullscreen {
+                state.states.set(xdg_toplevel::State::Fullscreen);
+                state.fullscreen_output = output;
+            } else {
+                state.states.unset(xdg_toplevel::State::Fullscreen);
+                state.fullscreen_output = None;
+            }
+        });
+        tracing::info!("Window {} {}", id, if fullscreen { "is fullscreen" } else { "left fullscreen" });
+
+        if fullscreen {
+            self.set_focus(Some(id));
+        }
+        self.reconfigure_windows();
+        // Minimized windows and those on other workspaces are left out above
+        self.configure_window(id);
+        true
+    }
+
+    /// Keep a window stacked above all others, or let it stack normally again
+    pub fn toggle_always_on_top(&mut self, id: WindowId) {
+        if let Some(window) = self.windows.get_mut(id) {
+            window.always_on_top = !window.always_on_top;
+            tracing::info!(
+                "Window {} {} kept on top",
+                id,
+                if window.always_on_top { "is" } else { "is no longer" }
+            );
+        }
+    }
+
+    /// Show another workspace, keeping the tiling state and focus of the one left behind
+    pub fn switch_workspace(&mut self, index: usize) {
+        if index >= self.workspaces.len() || index == self.active_workspace {
+            return;
+        }
+        self.move_grab = None;
+        self.end_resize();
+        self.window_menu = None;
+
+        let left = &mut self.workspaces[self.active_workspace];
+        std::mem::swap(&mut self.tree, &mut left.tree);
+        std::mem::swap(&mut self.strip, &mut left.strip);
+        left.focused = self.focused_window;
+
+        let shown = &mut self.workspaces[index];
+        std::mem::swap(&mut self.tree, &mut shown.tree);
+        std::mem::swap(&mut self.strip, &mut shown.strip);
+        let focused = shown.focused.take();
+        self.active_workspace = index;
+        tracing::info!("Switched to workspace {}", index + 1);
+
+        self.focused_window = None;
+        let focused = focused
+            .filter(|&id| self.is_window_visible(id))
+            .or_else(|| self.stacking_order().last().copied());
+        self.set_focus(focused);
+        self.reconfigure_windows();
+    }
+
+    /// Move a window to another workspace, taking it out of the shown one's layout
+    pub fn move_to_workspace(&mut self, id: WindowId, index: usize) {
+        let Some(window) = self.windows.get_mut(id) else {
+            return;
+        };
+        if index >= self.workspaces.len() || window.workspace == index {
+            return;
+        }
+        let from = std::mem::replace(&mut window.workspace, index);
+        let tiled = !window.is_floating() && !self.is_minimized(id);
+
+        if tiled {
+            if from == self.active_workspace {
+                self.tree.remove(id);
+                self.layout.remove_from_strip(&mut self.strip, id);
+            } else {
+                self.workspaces[from].tree.remove(id);
+                self.layout.remove_from_strip(&mut self.workspaces[from].strip, id);
+            }
+            if index == self.active_workspace {
+                let focused = self.focused_tiled_window();
+                self.tree.insert(id, focused);
+                self.strip.insert(id, focused);
+            } else {
+                self.workspaces[index].tree.insert(id, None);
+                self.workspaces[index].strip.insert(id, None);
+            }
+        }
+        tracing::info!("Moved window {} to workspace {}", id, index + 1);
+
+        if self.focused_window == Some(id) {
+            self.focused_window = None;
+            self.set_focus(self.stacking_order().last().copied());
+        }
+        self.reconfigure_windows();
+    }
+
+    /// Open the window menu of a window at a screen position
+    pub fn open_window_menu(&mut self, id: WindowId, pos: Point<i32, Logical>) {
+        let Some(window) = self.windows.get(id) else {
+            return;
+        };
+        let mut entries = vec![
+            MenuEntry::new(MenuItem::Close, "Close"),
+            MenuEntry::new(MenuItem::Minimize, "Minimize"),
+            MenuEntry::new(MenuItem::Maximize, "Maximize").checked(window.decoration.is_maximized),
+            MenuEntry::new(MenuItem::ToggleFloating, "Floating").checked(window.is_floating()),
+            MenuEntry::new(MenuItem::AlwaysOnTop, "Always on Top").checked(window.always_on_top),
+        ];
+        entries.extend(
+            (0..self.workspaces.len())
+                .filter(|&index| index != window.workspace)
+                .map(|index| MenuEntry::new(MenuItem::MoveToWorkspace(index), format!("Move to Workspace {}", index + 1))),
+        );
+
+        let bounds = Rectangle::from_size(self.layout.screen_size());
+        self.set_focus(Some(id));
+        self.window_menu = Some(WindowMenu::new(id, entries, pos, bounds));
+        tracing::info!("Opened window menu of window {}", id);
+    }
+
+    /// Close the window menu and carry out the chosen entry, if any
+    pub fn activate_menu_item(&mut self, item: Option<MenuItem>) {
+        let Some(menu) = self.window_menu.take() else {
+            return;
+        };
+        let id = menu.window;
+        match item {
+            Some(MenuItem::Close) => {
+                if let Some(window) = self.windows.get(id) {
+                    tracing::info!("Closing window {}", id);
+                    window.surface.send_close();
+                }
+            }
+            Some(MenuItem::Minimize) => self.minimize_window(id),
+            Some(MenuItem::Maximize) => self.toggle_maximized(id),
+            Some(MenuItem::ToggleFloating) => self.toggle_floating(id),
+            Some(MenuItem::AlwaysOnTop) => self.toggle_always_on_top(id),
+            Some(MenuItem::MoveToWorkspace(index)) => self.move_to_workspace(id, index),
+            None => {}
+        }
+    }
+
+    /// Navigate the open window menu with the keyboard. Returns false if the key isn't used by the menu.
+    pub fn window_menu_key(&mut self, keysym: Keysym) -> bool {
+        let Some(menu) = self.window_menu.as_mut() else {
+            return false;
+        };
+        match keysym {
+            Keysym::Up => menu.select_previous(),
+            Keysym::Down => menu.select_next(),
+            Keysym::Return | Keysym::KP_Enter | Keysym::space => {
+                let item = menu.selected_item();
+                if item.is_some() {
+                    self.activate_menu_item(item);
+                }
+            }
+            Keysym::Escape => self.activate_menu_item(None),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Let a window cover the usable area, or put it back in its place
+    pub fn toggle_maximized(&mut self, id: WindowId) {
+        self.set_maximized(id, !self.is_maximized(id));
+    }
+
+    /// Let a window cover the usable area above the other windows, or return it
+    /// to its slot in the layout. Returns false if nothing changed.
+    pub fn set_maximized(&mut self, id: WindowId, maximized: bool) -> bool {
+        let Some(window) = self.windows.get_mut(id) else {
+            return false;
+        };
+        if window.decoration.is_maximized == maximized {
+            return false;
+        }
+        window.decoration.is_maximized = maximized;
+        window.surface.with_pending_state(|state| {
+            if maximized {
+                state.states.set(xdg_toplevel::State::Maximized);
+            } else {
+                state.states.unset(xdg_toplevel::State::Maximized);
+            }
+        });
+        tracing::info!("Window {} {}", id, if maximized { "maximized" } else { "unmaximized" });
+
+        self.reconfigure_windows();
+        // Minimized windows and those on other workspaces are left out above
+        self.configure_window(id);
+        true
+    }
+
+    /// Stop managing a window: drop it from the layouts, hand focus to the
+    /// topmost remaining window and let the others take over its space
+    pub fn unmap_window(&mut self, id: WindowId) {
+        if self.windows.remove(id).is_none() {
+            return;
+        }
+        self.floating_stack.retain(|&i| i != id);
+        self.minimized.retain(|&i| i != id);
+        if self.move_grab.is_some_and(|grab| grab.window == id) {
+            self.move_grab = None;
+        }
+        if self.resize_grab.is_some_and(|grab| grab.window == id) {
+            self.resize_grab = None;
+        }
+        if self.window_menu.as_ref().is_some_and(|menu| menu.window == id) {
+            self.window_menu = None;
+        }
+        self.tree.remove(id);
+        self.layout.remove_from_strip(&mut self.strip, id);
+        for workspace in &mut self.workspaces {
+            workspace.tree.remove(id);
+            self.layout.remove_from_strip(&mut workspace.strip, id);
+        }
+        self.update_dock_running();
+        tracing::info!("Window {} unmapped", id);
+
+        if self.focused_window == Some(id) {
             self.focused_window = None;
-            tracing::info!("Window focus cleared");
+            self.set_focus(self.stacking_order().last().copied());
+        }
+
+        self.reconfigure_windows();
+    }
+
+    /// Unmap the windows whose surfaces went away with their client
+    pub fn remove_dead_windows(&mut self) {
+        let dead: Vec<WindowId> = self
+            .windows
+            .iter()
+            .filter(|window| !window.surface.alive())
+            .map(|window| window.id)
+            .collect();
+        for id in dead {
+            self.unmap_window(id);
+        }
+    }
+
+    /// Copy the title and app id the client set into the window's decoration
+    /// and metadata, keeping the previous title if the client never set one
+    fn update_toplevel_metadata(&mut self, id: WindowId) {
+        let Some(window) = self.windows.get_mut(id) else {
+            return;
+        };
+        let (title, app_id) = with_states(window.surface.wl_surface(), |states| {
+            let data = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            (data.title.clone(), data.app_id.clone())
+        });
+
+        if let Some(title) = title {
+            window.decoration.title = title;
+        }
+        if window.app_id != app_id {
+            window.app_id = app_id;
+            self.update_dock_running();
+            self.update_decoration_mode(id);
+        }
+    }
+
+    /// Decide who draws the window's decorations from what the client negotiated
+    /// and the config, and tell the client if it takes part in xdg-decoration
+    fn update_decoration_mode(&mut self, id: WindowId) {
+        let forced_apps = &self.config.force_server_decorations;
+        let Some(window) = self.windows.get_mut(id) else {
+            return;
+        };
+        let forced = window
+            .app_id
+            .as_ref()
+            .is_some_and(|app_id| forced_apps.contains(app_id));
+        let server_side = forced
+            || matches!(
+                window.decoration_preference,
+                DecorationPreference::NoPreference | DecorationPreference::ServerSide
+            );
+
+        if window.decoration_preference != DecorationPreference::Unsupported {
+            let mode = if server_side {
+                DecorationMode::ServerSide
+            } else {
+                DecorationMode::ClientSide
+            };
+            window.surface.with_pending_state(|state| {
+                state.decoration_mode = Some(mode);
+            });
+            if window.surface.is_initial_configure_sent() {
+                window.surface.send_pending_configure();
+            }
+        }
+
+        if window.decoration.is_server_side != server_side {
+            window.decoration.is_server_side = server_side;
+            tracing::info!(
+                "Window {} uses {} decorations",
+                id,
+                if server_side { "server-side" } else { "client-side" }
+            );
+            self.reconfigure_windows();
+        }
+    }
+
+    /// Record a window's decoration negotiation and apply the outcome
+    fn set_decoration_preference(&mut self, surface: &ToplevelSurface, preference: DecorationPreference) {
+        let Some(id) = self.windows.find_by_surface(surface.wl_surface()) else {
+            return;
+        };
+        if let Some(window) = self.windows.get_mut(id) {
+            window.decoration_preference = preference;
+        }
+        self.update_decoration_mode(id);
+    }
+
+    /// Show which dock apps currently have a window open
+    pub fn update_dock_running(&mut self) {
+        let app_ids: Vec<&str> = self
+            .windows
+            .iter()
+            .filter_map(|window| window.app_id.as_deref())
+            .collect();
+        self.dock.update_running(&app_ids);
+    }
+
+    /// Recompute the area available for tiling after the output size or dock changed
+    pub fn update_usable_area(&mut self) {
+        let screen = self.layout.screen_size();
+        let reserved = self.dock.exclusive_zone(screen.w, screen.h);
+        self.layout.set_reserved_bottom(reserved);
+    }
+
+    /// Send every shown toplevel a configure with the size the layout currently
+    /// gives it, and fit its decoration to that size
+    pub fn reconfigure_windows(&mut self) {
+        // Changes to the strip can leave the focused column out of view
+        self.scroll_to_focus();
+
+        let shown: Vec<WindowId> = self
+            .windows
+            .ids()
+            .into_iter()
+            .filter(|&id| !self.is_minimized(id) && self.is_on_active_workspace(id))
+            .collect();
+
+        for id in shown {
+            self.configure_window(id);
+        }
+    }
+
+    /// Fit a window's decoration to its place and configure the client with the
+    /// size below the title bar, which is the size a resize asks for while one is
+    /// waiting for the client. Returns the serial if a configure was sent.
+    fn configure_window(&mut self, id: WindowId) -> Option<Serial> {
+        let geom = self.constrained_geometry(id);
+        let requested = self
+            .resize_grab
+            .filter(|grab| grab.window == id)
+            .and_then(|grab| grab.configured)
+            .map_or(geom, |(_, requested)| requested);
+
+        let window = self.windows.get_mut(id)?;
+        window.decoration.width = geom.size.w;
+        window.decoration.height = geom.size.h;
+        let size = window.decoration.content_rect(requested.rect()).size;
+        window.surface.with_pending_state(|state| {
+            state.size = Some(size);
+        });
+        window.surface.send_pending_configure()
+    }
+
+    pub fn focused_tiled_window(&self) -> Option<WindowId> {
+        self.focused_window.filter(|&id| !self.is_floating(id))
+    }
+
+    /// Scroll the column strip so the focused window is visible
+    pub fn scroll_to_focus(&mut self) {
+        if self.layout.mode() != TilingMode::Scrolling {
+            return;
+        }
+        if let Some(id) = self.focused_tiled_window() {
+            let offset = self.strip.view_offset();
+            self.layout.scroll_to(&mut self.strip, id);
+            if self.strip.view_offset() != offset {
+                tracing::info!("Scrolled view to offset {}", self.strip.view_offset());
+            }
+        }
+    }
+
+    /// Focus the nearest window in the given direction from the focused one
+    pub fn focus_direction(&mut self, direction: Direction) {
+        let Some(current) = self.focused_window else {
+            self.set_focus(self.stacking_order().last().copied());
+            return;
+        };
+
+        let center = |id: WindowId| {
+            let rect = self.constrained_geometry(id).rect();
+            (rect.loc.x + rect.size.w / 2, rect.loc.y + rect.size.h / 2)
+        };
+        let (x, y) = center(current);
+
+        let target = self
+            .stacking_order()
+            .into_iter()
+            .filter(|&id| id != current)
+            .filter_map(|id| {
+                let (cx, cy) = center(id);
+                let (dx, dy) = ((cx - x) as i64, (cy - y) as i64);
+                let ahead = match direction {
+                    Direction::Left => dx < 0,
+                    Direction::Right => dx > 0,
+                    Direction::Up => dy < 0,
+                    Direction::Down => dy > 0,
+                };
+                ahead.then_some((id, dx * dx + dy * dy))
+            })
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(id, _)| id);
+
+        if target.is_some() {
+            self.set_focus(target);
+        }
+    }
+
+    /// Set focus to a specific window, showing it if it is behind a tab
+    /// and giving it keyboard focus
+    pub fn set_focus(&mut self, id: Option<WindowId>) {
+        match id {
+            Some(id) if self.windows.get(id).is_some() => {
+                self.focused_window = Some(id);
+                self.tree.activate(id);
+                self.raise_window(id);
+                self.scroll_to_focus();
+                tracing::info!("Window focus changed to {}", id);
+            }
+            Some(_) => {}
+            None => {
+                self.focused_window = None;
+                tracing::info!("Window focus cleared");
+            }
+        }
+
+        let focused = self.focused_window;
+        for window in self.windows.iter_mut() {
+            window.decoration.is_focused = Some(window.id) == focused;
+        }
+
+        if let Some(keyboard) = self.keyboard.clone() {
+            let surface = focused
+                .and_then(|id| self.windows.get(id))
+                .map(|window| window.surface.wl_surface().clone());
+            keyboard.set_focus(self, surface, SERIAL_COUNTER.next_serial());
         }
     }
 }
@@ -124,9 +1362,27 @@ impl CompositorHandler for MirageState {
         &client.get_data::<ClientState>().unwrap().compositor_state
     }
 
-    fn commit(&mut self, _surface: &WlSurface) {
+    fn commit(&mut self, surface: &WlSurface) {
         // Handle surface commit - surfaces are automatically updated by Smithay
         tracing::trace!("Surface commit received");
+
+        // Import the attached buffer so the surface can be rendered
+        on_commit_buffer_handler::<Self>(surface);
+
+        self.popups.commit(surface);
+        if let Some(PopupKind::Xdg(popup)) = self.popups.find_popup(surface) {
+            // The initial configure carries the position worked out in new_popup
+            if !popup.is_initial_configure_sent() {
+                if let Err(err) = popup.send_configure() {
+                    tracing::warn!("Failed to configure popup: {}", err);
+                }
+            }
+        }
+
+        if let Some(id) = self.windows.find_by_surface(surface) {
+            self.resize_committed(id);
+            self.update_size_hints(id);
+        }
     }
 }
 
@@ -139,73 +1395,223 @@ impl XdgShellHandler for MirageState {
 
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
         tracing::info!("New XDG toplevel window!");
-        let idx = self.windows.len();
-        self.windows.push(surface.clone());
-        
         // Create window decoration
-        let decoration = WindowDecoration {
-            title: format!("Window {}", idx + 1),
-            width: 800,
-            height: 600,
-            is_focused: true,
-            is_maximized: false,
-        };
-        self.decorations.push(decoration);
+        let title = format!("Window {}", self.windows.len() + 1);
+        let decoration = WindowDecoration::new(title, 800, 600, true);
+        let id = self.windows.insert(surface.clone(), decoration);
+        if let Some(window) = self.windows.get_mut(id) {
+            window.workspace = self.active_workspace;
+        }
+        self.update_toplevel_metadata(id);
+        self.update_decoration_mode(id);
+        self.tree.insert(id, self.focused_tiled_window());
+        self.strip.insert(id, self.focused_tiled_window());
         
-        // Configure the toplevel with the size from our layout
-        let geom = self.layout.calculate_geometry(idx, self.windows.len());
+        // Configure the toplevel with the size left below the title bar
+        let geom = self.content_geometry(id);
         
         // Send configure event with the allocated size
-        use smithay::utils::Size;
         let _ = surface.with_pending_state(|state| {
             state.size = Some(Size::<i32, Logical>::from((geom.size.w, geom.size.h)));
         });
         
         surface.send_configure();
+
+        // Existing windows give up space to the new one
+        self.reconfigure_windows();
         
         tracing::info!("Configured toplevel at ({},{}) with size {}x{}", 
                       geom.location.x, geom.location.y, geom.size.w, geom.size.h);
     }
 
-    fn new_popup(
-        &mut self,
-        _popup: smithay::wayland::shell::xdg::PopupSurface,
-        _positioner: smithay::wayland::shell::xdg::PositionerState,
-    ) {
-        println!("New XDG popup!");
+    fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
+        if let Some(id) = self.windows.find_by_surface(surface.wl_surface()) {
+            tracing::info!("Toplevel of window {} destroyed", id);
+            self.unmap_window(id);
+        }
+    }
+
+    fn title_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(id) = self.windows.find_by_surface(surface.wl_surface()) {
+            self.update_toplevel_metadata(id);
+            tracing::info!("Window {} title: {:?}", id, self.windows.get(id).map(|w| &w.decoration.title));
+        }
+    }
+
+    fn app_id_changed(&mut self, surface: ToplevelSurface) {
+        if let Some(id) = self.windows.find_by_surface(surface.wl_surface()) {
+            self.update_toplevel_metadata(id);
+            tracing::info!("Window {} app id: {:?}", id, self.windows.get(id).and_then(|w| w.app_id.as_deref()));
+        }
+    }
+
+    fn new_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
+        // The popup is configured on its first commit, with the geometry from its positioner
+        self.unconstrain_popup(&surface);
+        if let Err(err) = self.popups.track_popup(PopupKind::Xdg(surface)) {
+            tracing::warn!("Failed to track popup: {}", err);
+        }
     }
 
     fn move_request(
         &mut self,
-        _surface: smithay::wayland::shell::xdg::ToplevelSurface,
+        surface: smithay::wayland::shell::xdg::ToplevelSurface,
         _seat: smithay::reexports::wayland_server::protocol::wl_seat::WlSeat,
-        _serial: smithay::utils::Serial,
-    ) {}
+        serial: smithay::utils::Serial,
+    ) {
+        // Only a drag the user started on the window may move it
+        let Some(button) = self.pointer_grab_button(surface.wl_surface(), serial) else {
+            return;
+        };
+        if let Some(id) = self.windows.find_by_surface(surface.wl_surface()) {
+            self.begin_move(id, button);
+        }
+    }
 
     fn resize_request(
         &mut self,
-        _surface: smithay::wayland::shell::xdg::ToplevelSurface,
+        surface: smithay::wayland::shell::xdg::ToplevelSurface,
         _seat: smithay::reexports::wayland_server::protocol::wl_seat::WlSeat,
-        _serial: smithay::utils::Serial,
-        _edges: smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::ResizeEdge,
-    ) {}
+        serial: smithay::utils::Serial,
+        edges: smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::ResizeEdge,
+    ) {
+        // Only a drag the user started on the window may resize it
+        let Some(button) = self.pointer_grab_button(surface.wl_surface(), serial) else {
+            return;
+        };
+        if let Some(id) = self.windows.find_by_surface(surface.wl_surface()) {
+            self.begin_resize(id, edges.into(), button);
+        }
+    }
 
     fn grab(
         &mut self,
-        _popup: smithay::wayland::shell::xdg::PopupSurface,
-        _seat: smithay::reexports::wayland_server::protocol::wl_seat::WlSeat,
-        _serial: smithay::utils::Serial,
+        surface: PopupSurface,
+        seat: smithay::reexports::wayland_server::protocol::wl_seat::WlSeat,
+        serial: smithay::utils::Serial,
+    ) {
+        let Some(seat) = Seat::<Self>::from_resource(&seat) else {
+            return;
+        };
+        let popup = PopupKind::Xdg(surface);
+        let Ok(root) = find_popup_root_surface(&popup) else {
+            return;
+        };
+        let mut grab = match self.popups.grab_popup(root, popup, &seat, serial) {
+            Ok(grab) => grab,
+            Err(err) => {
+                tracing::warn!("Denied popup grab: {}", err);
+                return;
+            }
+        };
+
+        // Nested popups take over the grab of their parent, others need the click that opened them
+        let grab_serial = grab.previous_serial().unwrap_or(serial);
+        if let Some(keyboard) = seat.get_keyboard() {
+            if keyboard.is_grabbed() && !(keyboard.has_grab(serial) || keyboard.has_grab(grab_serial)) {
+                grab.ungrab(PopupUngrabStrategy::All);
+                return;
+            }
+            keyboard.set_focus(self, grab.current_grab(), serial);
+            keyboard.set_grab(self, PopupKeyboardGrab::new(&grab), serial);
+        }
+        if let Some(pointer) = seat.get_pointer() {
+            if pointer.is_grabbed() && !(pointer.has_grab(serial) || pointer.has_grab(grab_serial)) {
+                grab.ungrab(PopupUngrabStrategy::All);
+                return;
+            }
+            pointer.set_grab(self, PopupPointerGrab::new(&grab), serial, Focus::Keep);
+        }
+        self.popup_grab = Some(grab);
+    }
+
+    fn reposition_request(&mut self, surface: PopupSurface, positioner: PositionerState, token: u32) {
+        surface.with_pending_state(|state| {
+            state.geometry = positioner.get_geometry();
+            state.positioner = positioner;
+        });
+        self.unconstrain_popup(&surface);
+        surface.send_repositioned(token);
+    }
+
+    fn maximize_request(&mut self, surface: ToplevelSurface) {
+        let changed = match self.windows.find_by_surface(surface.wl_surface()) {
+            Some(id) => self.set_maximized(id, true),
+            None => false,
+        };
+        // The protocol demands a configure in reply, even if nothing changed
+        if !changed && surface.is_initial_configure_sent() {
+            surface.send_configure();
+        }
+    }
+
+    fn unmaximize_request(&mut self, surface: ToplevelSurface) {
+        let changed = match self.windows.find_by_surface(surface.wl_surface()) {
+            Some(id) => self.set_maximized(id, false),
+            None => false,
+        };
+        if !changed && surface.is_initial_configure_sent() {
+            surface.send_configure();
+        }
+    }
+
+    fn fullscreen_request(
+        &mut self,
+        surface: ToplevelSurface,
+        output: Option<WlOutput>,
     ) {
-        // TODO: Implement grab handling
+        let changed = match self.windows.find_by_surface(surface.wl_surface()) {
+            Some(id) => self.set_fullscreen(id, true, output),
+            None => false,
+        };
+        if !changed && surface.is_initial_configure_sent() {
+            surface.send_configure();
+        }
+    }
+
+    fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
+        let changed = match self.windows.find_by_surface(surface.wl_surface()) {
+            Some(id) => self.set_fullscreen(id, false, None),
+            None => false,
+        };
+        if !changed && surface.is_initial_configure_sent() {
+            surface.send_configure();
+        }
     }
 
-    fn reposition_request(
+    fn show_window_menu(
         &mut self,
-        _popup: smithay::wayland::shell::xdg::PopupSurface,
-        _positioner: smithay::wayland::shell::xdg::PositionerState,
-        _token: u32,
+        surface: ToplevelSurface,
+        _seat: smithay::reexports::wayland_server::protocol::wl_seat::WlSeat,
+        _serial: smithay::utils::Serial,
+        location: Point<i32, Logical>,
     ) {
-        // TODO: Implement reposition handling
+        // The location is relative to the client's surface
+        let Some(id) = self.windows.find_by_surface(surface.wl_surface()) else {
+            return;
+        };
+        let Some((_, origin)) = self.surface_placement(id) else {
+            return;
+        };
+        self.open_window_menu(id, origin + location);
+    }
+}
+
+impl XdgDecorationHandler for MirageState {
+    fn new_decoration(&mut self, toplevel: ToplevelSurface) {
+        self.set_decoration_preference(&toplevel, DecorationPreference::NoPreference);
+    }
+
+    fn request_mode(&mut self, toplevel: ToplevelSurface, mode: DecorationMode) {
+        let preference = match mode {
+            DecorationMode::ClientSide => DecorationPreference::ClientSide,
+            _ => DecorationPreference::ServerSide,
+        };
+        self.set_decoration_preference(&toplevel, preference);
+    }
+
+    fn unset_mode(&mut self, toplevel: ToplevelSurface) {
+        self.set_decoration_preference(&toplevel, DecorationPreference::NoPreference);
     }
 }
 
@@ -233,6 +1639,7 @@ impl ShmHandler for MirageState {
 
 smithay::delegate_compositor!(MirageState);
 smithay::delegate_xdg_shell!(MirageState);
+smithay::delegate_xdg_decoration!(MirageState);
 smithay::delegate_seat!(MirageState);
 smithay::delegate_output!(MirageState);
 smithay::delegate_shm!(MirageState);
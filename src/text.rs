@@ -0,0 +1,198 @@
+//! Text rendering for window titles
+//!
+//! Titles are shaped with cosmic-text, rasterized into an RGBA image and
+//! uploaded as a GLES texture. Textures are cached per title and width and
+//! dropped once a frame no longer draws them.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use cosmic_text::{Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, SwashCache};
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::backend::renderer::ImportMem;
+use unicode_segmentation::UnicodeSegmentation;
+
+const ELLIPSIS: &str = "…";
+
+/// Font used for title bar and tab text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleFont {
+    /// Family name, or one of the generic `sans-serif`, `serif` and `monospace`
+    pub family: String,
+    /// Size in pixels
+    pub size: f32,
+}
+
+impl Default for TitleFont {
+    fn default() -> Self {
+        Self {
+            family: "sans-serif".to_string(),
+            size: 13.0,
+        }
+    }
+}
+
+impl TitleFont {
+    fn attrs(&self) -> Attrs<'_> {
+        let family = match self.family.as_str() {
+            "sans-serif" => Family::SansSerif,
+            "serif" => Family::Serif,
+            "monospace" => Family::Monospace,
+            name => Family::Name(name),
+        };
+        Attrs::new().family(family)
+    }
+}
+
+/// Rasterizes titles into textures and keeps them around between frames
+pub struct TitleRenderer {
+    font: TitleFont,
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    textures: HashMap<(String, i32), GlesTexture>,
+    /// Cache entries drawn since the last [`TitleRenderer::end_frame`]
+    used: HashSet<(String, i32)>,
+}
+
+impl TitleRenderer {
+    pub fn new(font: TitleFont) -> Self {
+        Self {
+            font,
+            font_system: FontSystem::new(),
+            swash_cache: SwashCache::new(),
+            textures: HashMap::new(),
+            used: HashSet::new(),
+        }
+    }
+
+    /// Texture of the title, shortened with an ellipsis to fit `max_width`.
+    /// `None` if there is nothing to draw.
+    pub fn texture(&mut self, renderer: &mut GlesRenderer, title: &str, max_width: i32) -> Option<GlesTexture> {
+        if title.is_empty() || max_width <= 0 {
+            return None;
+        }
+
+        let key = (title.to_string(), max_width);
+        self.used.insert(key.clone());
+        if let Some(texture) = self.textures.get(&key) {
+            return Some(texture.clone());
+        }
+
+        let text = truncate_to_width(title, max_width as f32, |text| self.measure(text));
+        let (pixels, width, height) = self.rasterize(&text)?;
+        let texture = renderer
+            .import_memory(&pixels, Fourcc::Abgr8888, (width, height).into(), false)
+            .map_err(|err| tracing::warn!("Failed to upload title texture: {}", err))
+            .ok()?;
+
+        self.textures.insert(key, texture.clone());
+        Some(texture)
+    }
+
+    /// Drop the textures of titles that weren't drawn since the last call
+    pub fn end_frame(&mut self) {
+        let used = std::mem::take(&mut self.used);
+        self.textures.retain(|key, _| used.contains(key));
+    }
+
+    fn shape(&mut self, text: &str) -> Buffer {
+        let metrics = Metrics::new(self.font.size, (self.font.size * 1.3).ceil());
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+        buffer.set_size(&mut self.font_system, None, None);
+        buffer.set_text(&mut self.font_system, text, self.font.attrs(), Shaping::Advanced);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+        buffer
+    }
+
+    /// Width of the text on a single line
+    fn measure(&mut self, text: &str) -> f32 {
+        self.shape(text)
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max)
+    }
+
+    /// Draw the text in white into a premultiplied RGBA image
+    fn rasterize(&mut self, text: &str) -> Option<(Vec<u8>, i32, i32)> {
+        let buffer = self.shape(text);
+        let width = buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max).ceil() as i32;
+        let height = buffer.metrics().line_height.ceil() as i32;
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        buffer.draw(
+            &mut self.font_system,
+            &mut self.swash_cache,
+            Color::rgb(0xff, 0xff, 0xff),
+            |x, y, w, h, color| {
+                for py in y.max(0)..(y + h as i32).min(height) {
+                    for px in x.max(0)..(x + w as i32).min(width) {
+                        let alpha = color.a();
+                        let offset = ((py * width + px) * 4) as usize;
+                        let pixel = &mut pixels[offset..offset + 4];
+                        // Premultiplied, glyph coverage only ever adds up
+                        pixel[0] = pixel[0].max(mul(color.r(), alpha));
+                        pixel[1] = pixel[1].max(mul(color.g(), alpha));
+                        pixel[2] = pixel[2].max(mul(color.b(), alpha));
+                        pixel[3] = pixel[3].max(alpha);
+                    }
+                }
+            },
+        );
+
+        Some((pixels, width, height))
+    }
+}
+
+fn mul(channel: u8, alpha: u8) -> u8 {
+    ((channel as u16 * alpha as u16) / 255) as u8
+}
+
+/// Shorten text to the longest run of whole graphemes that fits `max_width`
+/// together with a trailing ellipsis. Text that fits is returned unchanged.
+pub fn truncate_to_width(text: &str, max_width: f32, mut measure: impl FnMut(&str) -> f32) -> Cow<'_, str> {
+    if text.is_empty() || measure(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+
+    // Byte offset of each grapheme, the text up to `cuts[n]` holds n graphemes
+    let cuts: Vec<usize> = text.grapheme_indices(true).map(|(index, _)| index).collect();
+    let shortened = |keep: usize| format!("{}{}", text[..cuts[keep]].trim_end(), ELLIPSIS);
+
+    // Binary search for the most graphemes that still fit
+    let (mut low, mut high) = (0, cuts.len().saturating_sub(1));
+    while low < high {
+        let keep = (low + high).div_ceil(2);
+        if measure(&shortened(keep)) <= max_width {
+            low = keep;
+        } else {
+            high = keep - 1;
+        }
+    }
+
+    Cow::Owned(shortened(low))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every grapheme is 10 pixels wide
+    fn width(text: &str) -> f32 {
+        text.graphemes(true).count() as f32 * 10.0
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("Terminal", 80.0, width), "Terminal");
+        assert_eq!(truncate_to_width("Terminal", 50.0, width), "Term…");
+        // Combining characters stay with their base letter
+        assert_eq!(truncate_to_width("cafe\u{301} au lait", 50.0, width), "cafe\u{301}…");
+        // Whitespace before the ellipsis is dropped
+        assert_eq!(truncate_to_width("ab cd", 40.0, width), "ab…");
+        assert_eq!(truncate_to_width("Terminal", 5.0, width), "…");
+    }
+}
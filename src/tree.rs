@@ -0,0 +1,942 @@
+//! Manual tiling - windows live in a tree of split containers (i3/sway style)
+//!
+//! Every container lays out its children side by side or on top of each other,
+//! or shows one child at a time behind a strip of tabs. A new window splits the
+//! focused window's container in the direction chosen with
+//! [`ContainerTree::set_next_split`].
+
+use smithay::utils::{Logical, Point, Rectangle};
+
+use crate::decorations::TITLE_BAR_HEIGHT;
+use crate::layout::{split_span, weighted_span};
+
+/// How a container arranges its children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Children side by side, left to right
+    Horizontal,
+    /// Children on top of each other, top to bottom
+    Vertical,
+}
+
+impl SplitDirection {
+    pub fn toggled(self) -> Self {
+        match self {
+            SplitDirection::Horizontal => SplitDirection::Vertical,
+            SplitDirection::Vertical => SplitDirection::Horizontal,
+        }
+    }
+}
+
+/// How a container presents its children
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presentation {
+    /// Children tiled next to each other along the container's split direction
+    Split,
+    /// Children share the container area, a row of tabs selects the visible one
+    Tabbed,
+    /// Like tabbed, but the tabs are title bars stacked on top of each other
+    Stacked,
+}
+
+impl Presentation {
+    pub fn next(self) -> Self {
+        match self {
+            Presentation::Split => Presentation::Tabbed,
+            Presentation::Tabbed => Presentation::Stacked,
+            Presentation::Stacked => Presentation::Split,
+        }
+    }
+}
+
+/// The tabs of a tabbed or stacked container, drawn in place of the title bar
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabStrip<T> {
+    pub rect: Rectangle<i32, Logical>,
+    /// One window per tab; a nested container is represented by its first window
+    pub tabs: Vec<T>,
+    /// Title of each tab, left empty by the tree and filled in from the windows' decorations
+    pub titles: Vec<String>,
+    /// Index of the tab whose window is shown
+    pub active: usize,
+    /// Tabs are stacked rows instead of a single row
+    pub stacked: bool,
+}
+
+impl<T: Copy> TabStrip<T> {
+    /// Screen area of a single tab
+    pub fn tab_rect(&self, index: usize) -> Rectangle<i32, Logical> {
+        if self.stacked {
+            Rectangle::new(
+                (self.rect.loc.x, self.rect.loc.y + index as i32 * TITLE_BAR_HEIGHT).into(),
+                (self.rect.size.w, TITLE_BAR_HEIGHT).into(),
+            )
+        } else {
+            let (x, width) = split_span(self.rect.size.w, index, self.tabs.len());
+            Rectangle::new(
+                (self.rect.loc.x + x, self.rect.loc.y).into(),
+                (width, self.rect.size.h).into(),
+            )
+        }
+    }
+
+    /// The window of the tab under the given point
+    pub fn tab_at(&self, point: Point<f64, Logical>) -> Option<T> {
+        let point = (point.x as i32, point.y as i32);
+        (0..self.tabs.len())
+            .find(|&index| self.tab_rect(index).contains(point))
+            .map(|index| self.tabs[index])
+    }
+}
+
+/// Smallest share of two neighbouring children either can get by moving the border between them
+const MIN_SPLIT_SHARE: f32 = 0.1;
+
+/// Direction to move a window in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// The container split a move in this direction travels along
+    fn split(self) -> SplitDirection {
+        match self {
+            Direction::Left | Direction::Right => SplitDirection::Horizontal,
+            Direction::Up | Direction::Down => SplitDirection::Vertical,
+        }
+    }
+
+    /// Whether the move goes towards the end of a container's children
+    fn is_forward(self) -> bool {
+        matches!(self, Direction::Right | Direction::Down)
+    }
+}
+
+/// A node in the container tree
+#[derive(Debug, Clone)]
+pub enum Node<T> {
+    Window(T),
+    Container(Container<T>),
+}
+
+impl<T: Copy + PartialEq> Node<T> {
+    fn contains(&self, window: T) -> bool {
+        match self {
+            Node::Window(candidate) => *candidate == window,
+            Node::Container(inner) => inner.children.iter().any(|child| child.contains(window)),
+        }
+    }
+
+    /// The window standing for this node in a tab strip
+    fn first_window(&self) -> Option<T> {
+        match self {
+            Node::Window(window) => Some(*window),
+            Node::Container(inner) => inner.children.iter().find_map(Node::first_window),
+        }
+    }
+}
+
+/// A split container holding windows and nested containers
+#[derive(Debug, Clone)]
+pub struct Container<T> {
+    pub split: SplitDirection,
+    pub presentation: Presentation,
+    /// Window whose child is shown in a tabbed or stacked container
+    pub active: Option<T>,
+    pub children: Vec<Node<T>>,
+    /// Relative sizes of the children of a split container. They only apply while
+    /// there is one per child, so adding or removing a child shares the space equally again.
+    pub weights: Vec<f32>,
+}
+
+impl<T: Copy + PartialEq> Container<T> {
+    fn new(split: SplitDirection) -> Self {
+        Self {
+            split,
+            presentation: Presentation::Split,
+            active: None,
+            children: Vec::new(),
+            weights: Vec::new(),
+        }
+    }
+
+    /// Relative size of every child
+    fn child_weights(&self) -> Vec<f32> {
+        if self.weights.len() == self.children.len() {
+            self.weights.clone()
+        } else {
+            vec![1.0; self.children.len()]
+        }
+    }
+
+    /// Area of a split container's child, given the container's area
+    fn child_rect(&self, index: usize, area: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let weights = self.child_weights();
+        match self.split {
+            SplitDirection::Horizontal => {
+                let (x, width) = weighted_span(area.size.w, index, &weights);
+                Rectangle::new((area.loc.x + x, area.loc.y).into(), (width, area.size.h).into())
+            }
+            SplitDirection::Vertical => {
+                let (y, height) = weighted_span(area.size.h, index, &weights);
+                Rectangle::new((area.loc.x, area.loc.y + y).into(), (area.size.w, height).into())
+            }
+        }
+    }
+
+    /// Area of the children of a tabbed or stacked container, below its tabs
+    fn tab_body(&self, area: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        // Stacked tabs take one row per child, so the shown child moves down by all but the last row
+        let rows = if self.presentation == Presentation::Stacked {
+            self.children.len() as i32
+        } else {
+            1
+        };
+        let offset = ((rows - 1) * TITLE_BAR_HEIGHT).clamp(0, (area.size.h - 1).max(0));
+        Rectangle::new(
+            (area.loc.x, area.loc.y + offset).into(),
+            (area.size.w, area.size.h - offset).into(),
+        )
+    }
+
+    /// Direction the children follow each other in, which is the direction moves travel along
+    fn axis(&self) -> SplitDirection {
+        match self.presentation {
+            Presentation::Split => self.split,
+            Presentation::Tabbed => SplitDirection::Horizontal,
+            Presentation::Stacked => SplitDirection::Vertical,
+        }
+    }
+
+    /// Index of the child shown in a tabbed or stacked container
+    fn active_index(&self) -> usize {
+        self.active
+            .and_then(|active| self.children.iter().position(|child| child.contains(active)))
+            .unwrap_or(0)
+    }
+}
+
+/// Result of laying out the tree
+struct Arrangement<T> {
+    /// Every window with its tile and whether it is shown
+    windows: Vec<(T, Rectangle<i32, Logical>, bool)>,
+    tab_strips: Vec<TabStrip<T>>,
+}
+
+/// Tree of split containers; `T` identifies a window
+#[derive(Debug, Clone)]
+pub struct ContainerTree<T> {
+    root: Container<T>,
+    next_split: SplitDirection,
+}
+
+impl<T: Copy + PartialEq> Default for ContainerTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + PartialEq> ContainerTree<T> {
+    pub fn new() -> Self {
+        Self {
+            root: Container::new(SplitDirection::Horizontal),
+            next_split: SplitDirection::Horizontal,
+        }
+    }
+
+    /// Split direction used when the next window is inserted
+    pub fn next_split(&self) -> SplitDirection {
+        self.next_split
+    }
+
+    pub fn set_next_split(&mut self, split: SplitDirection) {
+        self.next_split = split;
+    }
+
+    /// All windows in the tree, in depth-first order
+    pub fn windows(&self) -> Vec<T> {
+        fn collect<T: Copy>(container: &Container<T>, out: &mut Vec<T>) {
+            for child in &container.children {
+                match child {
+                    Node::Window(window) => out.push(*window),
+                    Node::Container(inner) => collect(inner, out),
+                }
+            }
+        }
+
+        let mut windows = Vec::new();
+        collect(&self.root, &mut windows);
+        windows
+    }
+
+    /// Add a window by splitting the container of `focused` in the next split direction.
+    /// Without a focused window in the tree, the root container is split instead.
+    pub fn insert(&mut self, window: T, focused: Option<T>) {
+        let split = self.next_split;
+
+        let Some(path) = focused.and_then(|focused| self.path_to(focused)) else {
+            if self.root.presentation != Presentation::Split {
+                self.root.children.push(Node::Window(window));
+                self.root.active = Some(window);
+                return;
+            }
+            if self.root.children.len() <= 1 {
+                self.root.split = split;
+            }
+            if self.root.split != split {
+                let old_root = std::mem::replace(&mut self.root, Container::new(split));
+                self.root.children.push(Node::Container(old_root));
+            }
+            self.root.children.push(Node::Window(window));
+            return;
+        };
+
+        let (&index, parent_path) = path.split_last().unwrap();
+        let parent = self.container_mut(parent_path);
+
+        // Tabbed and stacked containers get a new tab, shown right away
+        if parent.presentation != Presentation::Split {
+            parent.children.insert(index + 1, Node::Window(window));
+            parent.active = Some(window);
+            return;
+        }
+
+        // A container with a single child can simply change direction
+        if parent.children.len() == 1 {
+            parent.split = split;
+        }
+
+        if parent.split == split {
+            parent.children.insert(index + 1, Node::Window(window));
+        } else {
+            // Replace the focused window with a new container holding both windows
+            let mut container = Container::new(split);
+            let focused_node = std::mem::replace(&mut parent.children[index], Node::Window(window));
+            container.children.push(focused_node);
+            container.children.push(Node::Window(window));
+            parent.children[index] = Node::Container(container);
+        }
+    }
+
+    /// Remove a window, collapsing containers left empty or with a single child.
+    /// Returns false if the window isn't in the tree.
+    pub fn remove(&mut self, window: T) -> bool {
+        if !self.detach(window) {
+            return false;
+        }
+        self.simplify(false);
+        true
+    }
+
+    /// Flip the split direction of the container holding `window`
+    pub fn toggle_split(&mut self, window: T) {
+        if let Some(path) = self.path_to(window) {
+            let parent = self.container_mut(&path[..path.len() - 1]);
+            parent.split = parent.split.toggled();
+        }
+    }
+
+    /// Switch the container holding `window` to the next presentation: split, tabbed, stacked
+    pub fn cycle_presentation(&mut self, window: T) {
+        if let Some(path) = self.path_to(window) {
+            let parent = self.container_mut(&path[..path.len() - 1]);
+            parent.presentation = parent.presentation.next();
+            parent.active = Some(window);
+        }
+    }
+
+    /// Make sure the window is shown by selecting its tab in every tabbed or stacked ancestor
+    pub fn activate(&mut self, window: T) {
+        if let Some(path) = self.path_to(window) {
+            for depth in 0..path.len() {
+                self.container_mut(&path[..depth]).active = Some(window);
+            }
+        }
+    }
+
+    /// Select the next (or previous) tab of the closest tabbed or stacked container
+    /// holding `window`. Returns the window of the newly selected tab.
+    pub fn cycle_tab(&mut self, window: T, forward: bool) -> Option<T> {
+        let path = self.path_to(window)?;
+
+        for depth in (0..path.len()).rev() {
+            let container = self.container_mut(&path[..depth]);
+            if container.presentation == Presentation::Split {
+                continue;
+            }
+
+            let count = container.children.len();
+            let index = if forward {
+                (path[depth] + 1) % count
+            } else {
+                (path[depth] + count - 1) % count
+            };
+            let tab = container.children[index].first_window()?;
+            container.active = Some(tab);
+            return Some(tab);
+        }
+
+        None
+    }
+
+    /// Whether the window sits behind an unselected tab
+    pub fn is_hidden(&self, window: T) -> bool {
+        let Some(path) = self.path_to(window) else {
+            return false;
+        };
+
+        (0..path.len()).any(|depth| {
+            let container = self.container(&path[..depth]);
+            container.presentation != Presentation::Split && container.active_index() != path[depth]
+        })
+    }
+
+    /// Number of windows not hidden behind a tab
+    pub fn visible_count(&self) -> usize {
+        self.windows()
+            .into_iter()
+            .filter(|&window| !self.is_hidden(window))
+            .count()
+    }
+
+    /// Remove redundant nesting: single-child containers are replaced by their child,
+    /// and containers splitting the same way as their parent are merged into it
+    pub fn flatten(&mut self) {
+        self.simplify(true);
+    }
+
+    /// Exchange the places of two windows, including their tab selections.
+    /// Returns false unless both windows are in the tree.
+    pub fn swap_windows(&mut self, a: T, b: T) -> bool {
+        fn swap_in<T: Copy + PartialEq>(container: &mut Container<T>, a: T, b: T) {
+            let swapped = |window: T| if window == a { b } else if window == b { a } else { window };
+            container.active = container.active.map(swapped);
+            for child in &mut container.children {
+                match child {
+                    Node::Window(window) => *window = swapped(*window),
+                    Node::Container(inner) => swap_in(inner, a, b),
+                }
+            }
+        }
+
+        if a == b || self.path_to(a).is_none() || self.path_to(b).is_none() {
+            return false;
+        }
+        swap_in(&mut self.root, a, b);
+        true
+    }
+
+    /// Move the border of a window's tile on the given side to `position`, an x or
+    /// y coordinate in `area`, the area the whole tree is arranged in. The border is
+    /// shared with the neighbouring tile, so the two trade space. Returns false if
+    /// there is no neighbour on that side.
+    pub fn set_split_border(
+        &mut self,
+        window: T,
+        direction: Direction,
+        position: i32,
+        area: Rectangle<i32, Logical>,
+    ) -> bool {
+        let Some(path) = self.path_to(window) else {
+            return false;
+        };
+        let split = direction.split();
+
+        // Area of every container on the way down to the window
+        let mut areas = vec![area];
+        for depth in 0..path.len() - 1 {
+            let container = self.container(&path[..depth]);
+            let outer = areas[depth];
+            areas.push(if container.presentation == Presentation::Split {
+                container.child_rect(path[depth], outer)
+            } else {
+                container.tab_body(outer)
+            });
+        }
+
+        for depth in (0..path.len()).rev() {
+            let container = self.container(&path[..depth]);
+            if container.presentation != Presentation::Split || container.split != split {
+                continue;
+            }
+
+            // The children on both sides of the border
+            let index = path[depth];
+            let (first, second) = if direction.is_forward() {
+                if index + 1 >= container.children.len() {
+                    continue;
+                }
+                (index, index + 1)
+            } else {
+                if index == 0 {
+                    continue;
+                }
+                (index - 1, index)
+            };
+
+            let outer = areas[depth];
+            let (start, length) = match split {
+                SplitDirection::Horizontal => (outer.loc.x, outer.size.w),
+                SplitDirection::Vertical => (outer.loc.y, outer.size.h),
+            };
+            let mut weights = container.child_weights();
+            let (first_start, _) = weighted_span(length, first, &weights);
+            let (second_start, second_length) = weighted_span(length, second, &weights);
+            let pair_start = start + first_start;
+            let pair_length = (second_start + second_length - first_start).max(1);
+
+            let share = ((position - pair_start) as f32 / pair_length as f32).clamp(MIN_SPLIT_SHARE, 1.0 - MIN_SPLIT_SHARE);
+            let pair_weight = weights[first] + weights[second];
+            weights[first] = pair_weight * share;
+            weights[second] = pair_weight - weights[first];
+            self.container_mut(&path[..depth]).weights = weights;
+            return true;
+        }
+
+        false
+    }
+
+    /// Move a window to its neighbour in the given direction.
+    ///
+    /// A window next to another window swaps places with it, a window next to a
+    /// container moves into that container, and a window nested deeper moves out
+    /// into the closest ancestor container split along the direction. At the edge
+    /// of the tree the window is moved to the edge of a new root container.
+    /// Returns false if the window couldn't be moved.
+    pub fn move_window(&mut self, window: T, direction: Direction) -> bool {
+        let Some(path) = self.path_to(window) else {
+            return false;
+        };
+        let split = direction.split();
+        let forward = direction.is_forward();
+
+        for depth in (0..path.len()).rev() {
+            let container = self.container(&path[..depth]);
+            if container.axis() != split {
+                continue;
+            }
+
+            let index = path[depth];
+            let target = if forward {
+                Some(index + 1).filter(|&target| target < container.children.len())
+            } else {
+                index.checked_sub(1)
+            };
+            let Some(target) = target else {
+                continue;
+            };
+
+            if depth == path.len() - 1 {
+                // Direct neighbour in the window's own container
+                let container = self.container_mut(&path[..depth]);
+                let moved_into_container = match &mut container.children[target] {
+                    Node::Window(_) => false,
+                    Node::Container(inner) => {
+                        if forward {
+                            inner.children.insert(0, Node::Window(window));
+                        } else {
+                            inner.children.push(Node::Window(window));
+                        }
+                        true
+                    }
+                };
+                if moved_into_container {
+                    container.children.remove(index);
+                } else {
+                    container.children.swap(index, target);
+                }
+            } else {
+                // Move out of the nested container, next to it in this ancestor
+                self.detach(window);
+                let container = self.container_mut(&path[..depth]);
+                let position = if forward { index + 1 } else { index };
+                container.children.insert(position, Node::Window(window));
+            }
+
+            self.simplify(false);
+            return true;
+        }
+
+        if self.windows().len() <= 1 {
+            return false;
+        }
+
+        // Nothing to move past - wrap everything else in a container and put the window at its edge
+        self.detach(window);
+        let old_root = std::mem::replace(&mut self.root, Container::new(split));
+        let window = Node::Window(window);
+        let old_root = Node::Container(old_root);
+        self.root.children = if forward {
+            vec![old_root, window]
+        } else {
+            vec![window, old_root]
+        };
+        self.simplify(false);
+        true
+    }
+
+    /// Place every window of the tree inside the given area.
+    /// Windows hidden behind a tab share the tile of the shown one.
+    pub fn arrange(&self, area: Rectangle<i32, Logical>) -> Vec<(T, Rectangle<i32, Logical>)> {
+        self.layout(area)
+            .windows
+            .into_iter()
+            .map(|(window, rect, _)| (window, rect))
+            .collect()
+    }
+
+    /// Tab strips of all visible tabbed and stacked containers inside the given area
+    pub fn tab_strips(&self, area: Rectangle<i32, Logical>) -> Vec<TabStrip<T>> {
+        self.layout(area).tab_strips
+    }
+
+    fn layout(&self, area: Rectangle<i32, Logical>) -> Arrangement<T> {
+        fn place<T: Copy + PartialEq>(
+            container: &Container<T>,
+            area: Rectangle<i32, Logical>,
+            visible: bool,
+            out: &mut Arrangement<T>,
+        ) {
+            let count = container.children.len();
+
+            if container.presentation == Presentation::Split {
+                for (index, child) in container.children.iter().enumerate() {
+                    place_child(child, container.child_rect(index, area), visible, out);
+                }
+                return;
+            }
+
+            // Tabs replace the title bar of the shown child
+            let stacked = container.presentation == Presentation::Stacked;
+            let rows = if stacked { count as i32 } else { 1 };
+            let body = container.tab_body(area);
+            let active = container.active_index();
+
+            if visible {
+                out.tab_strips.push(TabStrip {
+                    rect: Rectangle::new(area.loc, (area.size.w, rows * TITLE_BAR_HEIGHT).into()),
+                    tabs: container.children.iter().filter_map(Node::first_window).collect(),
+                    titles: Vec::new(),
+                    active,
+                    stacked,
+                });
+            }
+
+            for (index, child) in container.children.iter().enumerate() {
+                place_child(child, body, visible && index == active, out);
+            }
+        }
+
+        fn place_child<T: Copy + PartialEq>(
+            child: &Node<T>,
+            rect: Rectangle<i32, Logical>,
+            visible: bool,
+            out: &mut Arrangement<T>,
+        ) {
+            match child {
+                Node::Window(window) => out.windows.push((*window, rect, visible)),
+                Node::Container(inner) => place(inner, rect, visible, out),
+            }
+        }
+
+        let mut arrangement = Arrangement {
+            windows: Vec::new(),
+            tab_strips: Vec::new(),
+        };
+        place(&self.root, area, true, &mut arrangement);
+        arrangement
+    }
+
+    /// Child indices leading from the root to the window
+    fn path_to(&self, window: T) -> Option<Vec<usize>> {
+        fn search<T: PartialEq>(container: &Container<T>, window: &T, path: &mut Vec<usize>) -> bool {
+            for (index, child) in container.children.iter().enumerate() {
+                path.push(index);
+                let found = match child {
+                    Node::Window(candidate) => candidate == window,
+                    Node::Container(inner) => search(inner, window, path),
+                };
+                if found {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        let mut path = Vec::new();
+        search(&self.root, &window, &mut path).then_some(path)
+    }
+
+    fn container(&self, path: &[usize]) -> &Container<T> {
+        let mut container = &self.root;
+        for &index in path {
+            container = match &container.children[index] {
+                Node::Container(inner) => inner,
+                Node::Window(_) => unreachable!("container path leads to a window"),
+            };
+        }
+        container
+    }
+
+    fn container_mut(&mut self, path: &[usize]) -> &mut Container<T> {
+        let mut container = &mut self.root;
+        for &index in path {
+            container = match &mut container.children[index] {
+                Node::Container(inner) => inner,
+                Node::Window(_) => unreachable!("container path leads to a window"),
+            };
+        }
+        container
+    }
+
+    /// Take a window out of its container without cleaning up the tree
+    fn detach(&mut self, window: T) -> bool {
+        let Some(path) = self.path_to(window) else {
+            return false;
+        };
+        let (&index, parent_path) = path.split_last().unwrap();
+        let parent = self.container_mut(parent_path);
+        parent.children.remove(index);
+
+        // Show the neighbouring tab when the shown one goes away
+        if parent.active == Some(window) {
+            let neighbour = index.min(parent.children.len().saturating_sub(1));
+            parent.active = parent.children.get(neighbour).and_then(Node::first_window);
+        }
+        true
+    }
+
+    /// Drop empty containers and unwrap single-child ones. With `merge_same_split`,
+    /// containers splitting like their parent are also merged into it.
+    fn simplify(&mut self, merge_same_split: bool) {
+        fn simplify_container<T: Copy + PartialEq>(container: &mut Container<T>, merge_same_split: bool) {
+            let children = std::mem::take(&mut container.children);
+            for child in children {
+                match child {
+                    Node::Window(_) => container.children.push(child),
+                    Node::Container(mut inner) => {
+                        simplify_container(&mut inner, merge_same_split);
+                        let same_split = inner.presentation == Presentation::Split
+                            && container.presentation == Presentation::Split
+                            && inner.split == container.split;
+                        let redundant = inner.children.len() == 1 || (merge_same_split && same_split);
+                        if redundant {
+                            container.children.extend(inner.children);
+                        } else if !inner.children.is_empty() {
+                            container.children.push(Node::Container(inner));
+                        }
+                    }
+                }
+            }
+        }
+
+        simplify_container(&mut self.root, merge_same_split);
+
+        // A root holding a single container is replaced by that container
+        if self.root.children.len() == 1 {
+            if let Node::Container(_) = self.root.children[0] {
+                let Some(Node::Container(inner)) = self.root.children.pop() else {
+                    unreachable!();
+                };
+                self.root = inner;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Rectangle<i32, Logical> {
+        Rectangle::new((0, 0).into(), (1000, 800).into())
+    }
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::new((x, y).into(), (w, h).into())
+    }
+
+    #[test]
+    fn test_split_focused_container() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.set_next_split(SplitDirection::Vertical);
+        tree.insert(3, Some(2));
+
+        assert_eq!(
+            tree.arrange(area()),
+            vec![
+                (1, rect(0, 0, 500, 800)),
+                (2, rect(500, 0, 500, 400)),
+                (3, rect(500, 400, 500, 400)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_collapses_containers() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.set_next_split(SplitDirection::Vertical);
+        tree.insert(3, Some(2));
+
+        assert!(tree.remove(2));
+        assert!(!tree.remove(2));
+        assert_eq!(tree.windows(), vec![1, 3]);
+        assert_eq!(tree.arrange(area())[1], (3, rect(500, 0, 500, 800)));
+    }
+
+    #[test]
+    fn test_toggle_split() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.toggle_split(2);
+        assert_eq!(tree.arrange(area())[1], (2, rect(0, 400, 1000, 400)));
+    }
+
+    #[test]
+    fn test_flatten_merges_same_split() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.set_next_split(SplitDirection::Vertical);
+        tree.insert(3, Some(2));
+        // The nested container now splits like the root
+        tree.toggle_split(3);
+        tree.flatten();
+
+        let placed = tree.arrange(area());
+        assert_eq!(placed.len(), 3);
+        assert_eq!(placed[2], (3, rect(666, 0, 334, 800)));
+    }
+
+    #[test]
+    fn test_tabbed_container() {
+        let mut tree = ContainerTree::new();
+        for window in 1..=3 {
+            tree.insert(window, None);
+        }
+        tree.cycle_presentation(2);
+
+        // All windows share the full area, only the active tab is shown
+        assert!(tree.arrange(area()).iter().all(|(_, r)| *r == area()));
+        assert!(tree.is_hidden(1));
+        assert!(!tree.is_hidden(2));
+        assert_eq!(tree.visible_count(), 1);
+
+        let strips = tree.tab_strips(area());
+        assert_eq!(strips.len(), 1);
+        assert_eq!(strips[0].tabs, vec![1, 2, 3]);
+        assert_eq!(strips[0].active, 1);
+        assert_eq!(strips[0].tab_rect(2), rect(666, 0, 334, TITLE_BAR_HEIGHT));
+        assert_eq!(strips[0].tab_at(Point::from((10.0, 10.0))), Some(1));
+
+        assert_eq!(tree.cycle_tab(2, true), Some(3));
+        assert_eq!(tree.cycle_tab(3, true), Some(1));
+        assert!(!tree.is_hidden(1));
+
+        // A new window becomes a new, visible tab
+        tree.insert(4, Some(1));
+        assert_eq!(tree.windows(), vec![1, 4, 2, 3]);
+        assert!(!tree.is_hidden(4));
+
+        // Closing the shown tab reveals its neighbour
+        tree.remove(4);
+        assert!(!tree.is_hidden(2));
+    }
+
+    #[test]
+    fn test_stacked_container() {
+        let mut tree = ContainerTree::new();
+        for window in 1..=3 {
+            tree.insert(window, None);
+        }
+        tree.cycle_presentation(1);
+        tree.cycle_presentation(1);
+
+        // Three title bar rows; the shown window's own title bar sits under the last one
+        let strip = &tree.tab_strips(area())[0];
+        assert!(strip.stacked);
+        assert_eq!(strip.rect, rect(0, 0, 1000, 3 * TITLE_BAR_HEIGHT));
+        assert_eq!(strip.tab_rect(1), rect(0, TITLE_BAR_HEIGHT, 1000, TITLE_BAR_HEIGHT));
+        let body = rect(0, 2 * TITLE_BAR_HEIGHT, 1000, 800 - 2 * TITLE_BAR_HEIGHT);
+        assert_eq!(tree.arrange(area())[0], (1, body));
+    }
+
+    #[test]
+    fn test_set_split_border() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.set_next_split(SplitDirection::Vertical);
+        tree.insert(3, Some(2));
+
+        // Dragging the left border of 3 resizes the outer split between 1 and the 2/3 column
+        assert!(tree.set_split_border(3, Direction::Left, 300, area()));
+        assert!(tree.set_split_border(2, Direction::Down, 600, area()));
+        assert_eq!(
+            tree.arrange(area()),
+            vec![
+                (1, rect(0, 0, 300, 800)),
+                (2, rect(300, 0, 700, 600)),
+                (3, rect(300, 600, 700, 200)),
+            ]
+        );
+
+        // Neither tile can be squeezed away, and there is nothing left of 1
+        assert!(tree.set_split_border(1, Direction::Right, 0, area()));
+        assert_eq!(tree.arrange(area())[0].1, rect(0, 0, 100, 800));
+        assert!(!tree.set_split_border(1, Direction::Left, 0, area()));
+
+        // A new window shares the space equally again
+        tree.insert(4, Some(3));
+        assert_eq!(tree.arrange(area())[1].1.size.h, 800 / 3);
+    }
+
+    #[test]
+    fn test_swap_windows() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.set_next_split(SplitDirection::Vertical);
+        tree.insert(3, Some(2));
+        tree.cycle_presentation(3);
+
+        assert!(tree.swap_windows(1, 3));
+        assert_eq!(tree.windows(), vec![3, 2, 1]);
+        // The tab selection follows the window to its new place
+        assert!(!tree.is_hidden(1));
+        assert!(tree.is_hidden(2));
+        assert!(!tree.swap_windows(1, 4));
+    }
+
+    #[test]
+    fn test_move_window() {
+        let mut tree = ContainerTree::new();
+        tree.insert(1, None);
+        tree.insert(2, Some(1));
+        tree.set_next_split(SplitDirection::Vertical);
+        tree.insert(3, Some(2));
+
+        // Out of the nested container, to the left of it
+        assert!(tree.move_window(3, Direction::Left));
+        assert_eq!(tree.windows(), vec![1, 3, 2]);
+        assert_eq!(tree.arrange(area())[1], (3, rect(333, 0, 333, 800)));
+
+        // Swap with a neighbouring window
+        assert!(tree.move_window(3, Direction::Left));
+        assert_eq!(tree.windows(), vec![3, 1, 2]);
+
+        // At the edge, a vertical move wraps the rest in a new container
+        assert!(tree.move_window(3, Direction::Up));
+        assert_eq!(tree.arrange(area())[0], (3, rect(0, 0, 1000, 400)));
+    }
+}
@@ -0,0 +1,145 @@
+//! Registry of the toplevel windows the compositor manages
+//!
+//! Every window gets a [`WindowId`] when it is mapped. Ids are never reused, so
+//! focus, layouts and the floating stack can hold on to them while other
+//! windows come and go without ending up pointing at the wrong window.
+
+use std::fmt;
+
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::wayland::shell::xdg::ToplevelSurface;
+
+use crate::decorations::WindowDecoration;
+use crate::layout::{SizeHints, WindowGeometry};
+
+/// Stable identifier of a managed window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(u64);
+
+impl fmt::Display for WindowId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// What a client said about decorations over xdg-decoration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecorationPreference {
+    /// The client never negotiated, so it draws its own decorations
+    #[default]
+    Unsupported,
+    /// The client leaves the choice to the compositor
+    NoPreference,
+    ClientSide,
+    ServerSide,
+}
+
+/// A toplevel together with everything the compositor tracks about it
+#[derive(Debug)]
+pub struct ManagedWindow {
+    pub id: WindowId,
+    pub surface: ToplevelSurface,
+    pub decoration: WindowDecoration,
+    /// Decoration mode the client negotiated
+    pub decoration_preference: DecorationPreference,
+    /// Application id the client set, used to match the window to dock apps and rules
+    pub app_id: Option<String>,
+    /// Geometry while the window floats above the tiling layout, `None` when tiled
+    pub floating: Option<WindowGeometry>,
+    /// Last min/max size the client committed
+    pub size_hints: SizeHints,
+    /// Index of the workspace the window is on
+    pub workspace: usize,
+    /// Stacked above all other windows
+    pub always_on_top: bool,
+}
+
+impl ManagedWindow {
+    pub fn is_floating(&self) -> bool {
+        self.floating.is_some()
+    }
+}
+
+/// All managed windows in the order they were opened
+#[derive(Debug, Default)]
+pub struct WindowRegistry {
+    windows: Vec<ManagedWindow>,
+    next_id: u64,
+}
+
+impl WindowRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start managing a toplevel, returning its new id
+    pub fn insert(&mut self, surface: ToplevelSurface, decoration: WindowDecoration) -> WindowId {
+        self.next_id += 1;
+        let id = WindowId(self.next_id);
+        self.windows.push(ManagedWindow {
+            id,
+            surface,
+            decoration,
+            decoration_preference: DecorationPreference::default(),
+            app_id: None,
+            floating: None,
+            size_hints: SizeHints::default(),
+            workspace: 0,
+            always_on_top: false,
+        });
+        id
+    }
+
+    /// Stop managing a window, handing back what was tracked about it
+    pub fn remove(&mut self, id: WindowId) -> Option<ManagedWindow> {
+        let pos = self.windows.iter().position(|window| window.id == id)?;
+        Some(self.windows.remove(pos))
+    }
+
+    /// Exchange the opening order positions of two windows, which is the order
+    /// the automatic layout tiles them in
+    pub fn swap(&mut self, a: WindowId, b: WindowId) {
+        let pos_a = self.windows.iter().position(|window| window.id == a);
+        let pos_b = self.windows.iter().position(|window| window.id == b);
+        if let (Some(pos_a), Some(pos_b)) = (pos_a, pos_b) {
+            self.windows.swap(pos_a, pos_b);
+        }
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&ManagedWindow> {
+        self.windows.iter().find(|window| window.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut ManagedWindow> {
+        self.windows.iter_mut().find(|window| window.id == id)
+    }
+
+    /// The window whose toplevel uses the given surface
+    pub fn find_by_surface(&self, surface: &WlSurface) -> Option<WindowId> {
+        self.windows
+            .iter()
+            .find(|window| window.surface.wl_surface() == surface)
+            .map(|window| window.id)
+    }
+
+    /// Ids of all windows in opening order
+    pub fn ids(&self) -> Vec<WindowId> {
+        self.windows.iter().map(|window| window.id).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ManagedWindow> {
+        self.windows.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ManagedWindow> {
+        self.windows.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+}
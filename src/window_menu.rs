@@ -0,0 +1,161 @@
+//! Context menu of a window, opened from its title bar
+//!
+//! The menu only tracks its entries, placement and selection; the compositor
+//! state carries out the chosen entry and the backend draws it.
+
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+const MENU_WIDTH: i32 = 200;
+const ENTRY_HEIGHT: i32 = 26;
+/// Space between the menu border and its entries
+pub const MENU_PADDING: i32 = 5;
+
+/// What choosing a menu entry does to the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Close,
+    Minimize,
+    Maximize,
+    ToggleFloating,
+    AlwaysOnTop,
+    /// Move the window to the workspace with this index
+    MoveToWorkspace(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuEntry {
+    pub item: MenuItem,
+    pub label: String,
+    /// Whether the entry toggles a setting that is currently on
+    pub checked: bool,
+}
+
+impl MenuEntry {
+    pub fn new(item: MenuItem, label: impl Into<String>) -> Self {
+        Self {
+            item,
+            label: label.into(),
+            checked: false,
+        }
+    }
+
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+}
+
+/// An open window menu; `T` identifies the window it acts on
+#[derive(Debug, Clone)]
+pub struct WindowMenu<T> {
+    pub window: T,
+    pub entries: Vec<MenuEntry>,
+    /// Where the menu is on screen
+    pub rect: Rectangle<i32, Logical>,
+    /// Highlighted entry, chosen by Enter
+    pub selected: Option<usize>,
+}
+
+impl<T> WindowMenu<T> {
+    /// Open the menu with its top-left corner at `pos`, moved as needed to fit inside `bounds`
+    pub fn new(window: T, entries: Vec<MenuEntry>, pos: Point<i32, Logical>, bounds: Rectangle<i32, Logical>) -> Self {
+        let size = Size::from((MENU_WIDTH, entries.len() as i32 * ENTRY_HEIGHT + 2 * MENU_PADDING));
+        let x = pos.x.min(bounds.loc.x + bounds.size.w - size.w).max(bounds.loc.x);
+        let y = pos.y.min(bounds.loc.y + bounds.size.h - size.h).max(bounds.loc.y);
+        Self {
+            window,
+            entries,
+            rect: Rectangle::new((x, y).into(), size),
+            selected: None,
+        }
+    }
+
+    /// Screen area of an entry
+    pub fn entry_rect(&self, index: usize) -> Rectangle<i32, Logical> {
+        Rectangle::new(
+            (
+                self.rect.loc.x + MENU_PADDING,
+                self.rect.loc.y + MENU_PADDING + index as i32 * ENTRY_HEIGHT,
+            )
+                .into(),
+            (self.rect.size.w - 2 * MENU_PADDING, ENTRY_HEIGHT).into(),
+        )
+    }
+
+    /// Index of the entry under a screen position
+    pub fn entry_at(&self, point: Point<f64, Logical>) -> Option<usize> {
+        (0..self.entries.len()).find(|&index| {
+            let rect = self.entry_rect(index).to_f64();
+            rect.contains(point)
+        })
+    }
+
+    pub fn contains(&self, point: Point<f64, Logical>) -> bool {
+        self.rect.to_f64().contains(point)
+    }
+
+    /// Highlight the next entry, wrapping around at the end
+    pub fn select_next(&mut self) {
+        let count = self.entries.len();
+        if count > 0 {
+            self.selected = Some(self.selected.map_or(0, |index| (index + 1) % count));
+        }
+    }
+
+    /// Highlight the previous entry, wrapping around at the start
+    pub fn select_previous(&mut self) {
+        let count = self.entries.len();
+        if count > 0 {
+            self.selected = Some(self.selected.map_or(count - 1, |index| (index + count - 1) % count));
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<MenuItem> {
+        self.selected
+            .and_then(|index| self.entries.get(index))
+            .map(|entry| entry.item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(pos: (i32, i32)) -> WindowMenu<u32> {
+        let entries = vec![
+            MenuEntry::new(MenuItem::Close, "Close"),
+            MenuEntry::new(MenuItem::Minimize, "Minimize"),
+            MenuEntry::new(MenuItem::AlwaysOnTop, "Always on Top").checked(true),
+        ];
+        WindowMenu::new(1, entries, pos.into(), Rectangle::new((0, 0).into(), (800, 600).into()))
+    }
+
+    #[test]
+    fn test_menu_fits_bounds() {
+        assert_eq!(menu((100, 50)).rect.loc, Point::from((100, 50)));
+        // Pushed back inside near the bottom-right corner
+        let menu = menu((790, 590));
+        assert_eq!(menu.rect.loc, Point::from((600, 600 - 3 * ENTRY_HEIGHT - 2 * MENU_PADDING)));
+    }
+
+    #[test]
+    fn test_entry_at() {
+        let menu = menu((100, 50));
+        let second = menu.entry_rect(1);
+        assert_eq!(menu.entry_at(Point::from((150.0, second.loc.y as f64 + 1.0))), Some(1));
+        assert_eq!(menu.entry_at(Point::from((150.0, 51.0))), None);
+        assert!(menu.contains(Point::from((150.0, 51.0))));
+        assert_eq!(menu.entry_at(Point::from((20.0, 60.0))), None);
+    }
+
+    #[test]
+    fn test_keyboard_selection_wraps() {
+        let mut menu = menu((0, 0));
+        menu.select_previous();
+        assert_eq!(menu.selected_item(), Some(MenuItem::AlwaysOnTop));
+        menu.select_next();
+        assert_eq!(menu.selected_item(), Some(MenuItem::Close));
+        menu.select_next();
+        assert_eq!(menu.selected_item(), Some(MenuItem::Minimize));
+    }
+}
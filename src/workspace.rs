@@ -0,0 +1,20 @@
+//! Workspaces: separate sets of windows, one of them shown at a time
+//!
+//! The tiling state of the shown workspace lives directly in the compositor
+//! state. Hidden workspaces keep theirs here until they are switched to.
+
+use crate::scrolling::ScrollingLayout;
+use crate::tree::ContainerTree;
+use crate::window::WindowId;
+
+/// Number of workspaces, reachable with Super+1 to Super+4
+pub const WORKSPACE_COUNT: usize = 4;
+
+/// Tiling state of a workspace while it isn't shown
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pub tree: ContainerTree<WindowId>,
+    pub strip: ScrollingLayout<WindowId>,
+    /// Window that had focus when the workspace was left
+    pub focused: Option<WindowId>,
+}